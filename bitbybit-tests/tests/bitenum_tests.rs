@@ -1,4 +1,4 @@
-use arbitrary_int::{u2, u63};
+use arbitrary_int::{u1, u2, u3, u63};
 use bitbybit::bitenum;
 
 #[test]
@@ -106,6 +106,7 @@ fn enum_with_16bits() {
 fn enum_with_32bits() {
     #[bitenum(u32, exhaustive: false)]
     #[derive(Eq, PartialEq, Debug)]
+    #[repr(u32)]
     enum Foo {
         Zero = 0,
         One = 0xFFFFFFFF,
@@ -199,3 +200,273 @@ fn documentation() {
     assert_eq!(Foo::Zero.raw_value(), u2::new(0));
     assert_eq!(Foo::One.raw_value(), u2::new(1));
 }
+
+#[test]
+fn fallback() {
+    #[bitenum(u3, exhaustive: false, fallback: Unknown)]
+    #[derive(Eq, PartialEq, Debug)]
+    enum Foo {
+        Zero = 0,
+        One = 1,
+        Five = 0b101,
+        Unknown(u3),
+    }
+
+    assert_eq!(Foo::new_with_raw_value(u3::new(0)), Foo::Zero);
+    assert_eq!(Foo::new_with_raw_value(u3::new(1)), Foo::One);
+    assert_eq!(Foo::new_with_raw_value(u3::new(5)), Foo::Five);
+    assert_eq!(
+        Foo::new_with_raw_value(u3::new(2)),
+        Foo::Unknown(u3::new(2))
+    );
+    assert_eq!(
+        Foo::new_with_raw_value(u3::new(7)),
+        Foo::Unknown(u3::new(7))
+    );
+
+    assert_eq!(Foo::Zero.raw_value(), u3::new(0));
+    assert_eq!(Foo::Five.raw_value(), u3::new(5));
+    assert_eq!(Foo::Unknown(u3::new(6)).raw_value(), u3::new(6));
+
+    // Every raw value survives the round trip
+    for i in 0..8 {
+        assert_eq!(Foo::new_with_raw_value(u3::new(i)).raw_value(), u3::new(i));
+    }
+
+    // A fallback value that is created directly isn't equal to the variant with the same raw value,
+    // but new_with_raw_value() turns it into that variant
+    assert_ne!(Foo::Unknown(u3::new(0)), Foo::Zero);
+    assert_eq!(
+        Foo::new_with_raw_value(Foo::Unknown(u3::new(0)).raw_value()),
+        Foo::Zero
+    );
+}
+
+#[test]
+fn fallback_with_8bits() {
+    #[bitenum(u8, fallback: Other)]
+    #[derive(Eq, PartialEq, Debug)]
+    enum Foo {
+        A = 0x10,
+        B = 0x20,
+        Other(u8),
+    }
+
+    assert_eq!(Foo::new_with_raw_value(0x10), Foo::A);
+    assert_eq!(Foo::new_with_raw_value(0x21), Foo::Other(0x21));
+    assert_eq!(Foo::B.raw_value(), 0x20);
+    assert_eq!(Foo::Other(0xFF).raw_value(), 0xFF);
+}
+
+#[test]
+fn variant_enumeration() {
+    #[bitenum(u2, exhaustive: false)]
+    #[derive(Eq, PartialEq, Debug)]
+    enum Foo {
+        Two = 2,
+        Zero = 0,
+        One = 1,
+    }
+
+    assert_eq!(Foo::COUNT, 3);
+    assert_eq!(Foo::ALL, [Foo::Two, Foo::Zero, Foo::One]);
+    assert_eq!(
+        Foo::iter().collect::<Vec<_>>(),
+        vec![Foo::Two, Foo::Zero, Foo::One]
+    );
+    assert_eq!(Foo::variant(1), Some(Foo::Zero));
+    assert_eq!(Foo::variant(3), None);
+
+    // Iterating in a const context
+    const RAW_VALUE_SUM: u8 = {
+        let mut sum = 0;
+        let mut i = 0;
+        while let Some(variant) = Foo::variant(i) {
+            sum += variant.raw_value().value();
+            i += 1;
+        }
+        sum
+    };
+    assert_eq!(RAW_VALUE_SUM, 3);
+}
+
+#[test]
+fn variant_enumeration_with_fallback() {
+    #[bitenum(u3, exhaustive: false, fallback: Unknown)]
+    #[derive(Eq, PartialEq, Debug)]
+    enum Foo {
+        Zero = 0,
+        One = 1,
+        Unknown(u3),
+    }
+
+    // The fallback variant isn't listed
+    assert_eq!(Foo::COUNT, 2);
+    assert_eq!(Foo::ALL, [Foo::Zero, Foo::One]);
+    for variant in Foo::iter() {
+        assert_eq!(Foo::new_with_raw_value(variant.raw_value()), variant);
+    }
+}
+
+#[test]
+fn names() {
+    #[bitenum(u2, exhaustive: false, display, from_str)]
+    #[derive(Eq, PartialEq, Debug)]
+    enum Foo {
+        Zero = 0,
+        /// Documentation is kept next to a name
+        #[name = "ONE_SHOT"]
+        One = 1,
+        Two = 2,
+    }
+
+    const NAME: &str = Foo::One.name();
+    assert_eq!(NAME, "ONE_SHOT");
+    assert_eq!(Foo::Zero.name(), "Zero");
+
+    assert_eq!(Foo::Two.to_string(), "Two");
+    assert_eq!(format!("{}", Foo::One), "ONE_SHOT");
+
+    assert_eq!("Zero".parse::<Foo>(), Ok(Foo::Zero));
+    assert_eq!("ONE_SHOT".parse::<Foo>(), Ok(Foo::One));
+    assert_eq!("One".parse::<Foo>(), Err(ParseFooError));
+    assert_eq!("zero".parse::<Foo>(), Err(ParseFooError));
+    assert_eq!(ParseFooError.to_string(), "unknown variant of Foo");
+}
+
+#[test]
+fn name_function_is_opt_in() {
+    #[bitenum(u1, exhaustive: true, name)]
+    enum Level {
+        #[name = "low"]
+        Low = 0,
+        High = 1,
+    }
+    assert_eq!(Level::Low.name(), "low");
+    assert_eq!(Level::High.name(), "High");
+
+    // Without name(), a variant can be called name
+    #[bitenum(u1, exhaustive: true)]
+    #[allow(non_camel_case_types)]
+    #[derive(Eq, PartialEq, Debug)]
+    enum Field {
+        name = 0,
+        value = 1,
+    }
+    assert_eq!(Field::new_with_raw_value(u1::new(0)), Field::name);
+}
+
+#[test]
+fn names_case_insensitive() {
+    #[bitenum(u3, exhaustive: false, fallback: Unknown, display, from_str: case_insensitive)]
+    #[derive(Eq, PartialEq, Debug)]
+    enum Foo {
+        #[name = "Rd"]
+        Read = 0,
+        Write = 1,
+        Unknown(u3),
+    }
+
+    assert_eq!("rd".parse::<Foo>(), Ok(Foo::Read));
+    assert_eq!("WRITE".parse::<Foo>(), Ok(Foo::Write));
+    assert_eq!("Read".parse::<Foo>(), Err(ParseFooError));
+    // The fallback variant can't be parsed, as it needs a value
+    assert_eq!("Unknown".parse::<Foo>(), Err(ParseFooError));
+
+    assert_eq!(Foo::Unknown(u3::new(5)).name(), "Unknown");
+    assert_eq!(Foo::Unknown(u3::new(5)).to_string(), "Unknown(5)");
+    assert_eq!(Foo::Read.to_string(), "Rd");
+}
+
+#[test]
+fn implicit_values() {
+    #[bitenum(u3, exhaustive: false)]
+    #[derive(Eq, PartialEq, Debug)]
+    enum Foo {
+        Zero,
+        One,
+        Five = 5,
+        Six,
+    }
+
+    assert_eq!(Foo::new_with_raw_value(u3::new(0)), Ok(Foo::Zero));
+    assert_eq!(Foo::new_with_raw_value(u3::new(1)), Ok(Foo::One));
+    assert_eq!(Foo::new_with_raw_value(u3::new(5)), Ok(Foo::Five));
+    assert_eq!(Foo::new_with_raw_value(u3::new(6)), Ok(Foo::Six));
+    assert_eq!(Foo::new_with_raw_value(u3::new(7)), Err(7));
+    assert_eq!(Foo::Six.raw_value(), u3::new(6));
+
+    #[bitenum(u2, exhaustive: true)]
+    #[derive(Eq, PartialEq, Debug)]
+    enum Exhaustive {
+        Zero,
+        One,
+        Two,
+        Three,
+    }
+
+    assert_eq!(
+        Exhaustive::new_with_raw_value(u2::new(3)),
+        Exhaustive::Three
+    );
+    assert_eq!(Exhaustive::Two.raw_value(), u2::new(2));
+}
+
+#[test]
+fn const_expression_values() {
+    const BASE: u8 = 4;
+
+    #[bitenum(u3, exhaustive: false)]
+    #[derive(Eq, PartialEq, Debug)]
+    #[repr(u8)]
+    enum Foo {
+        Zero = 0,
+        Base = BASE,
+        AfterBase,
+        Last = BASE + 3,
+    }
+
+    assert_eq!(Foo::new_with_raw_value(u3::new(0)), Ok(Foo::Zero));
+    assert_eq!(Foo::new_with_raw_value(u3::new(4)), Ok(Foo::Base));
+    assert_eq!(Foo::new_with_raw_value(u3::new(5)), Ok(Foo::AfterBase));
+    assert_eq!(Foo::new_with_raw_value(u3::new(7)), Ok(Foo::Last));
+    assert_eq!(Foo::new_with_raw_value(u3::new(6)), Err(6));
+    assert_eq!(Foo::AfterBase.raw_value(), u3::new(5));
+    assert_eq!(Foo::Last.raw_value(), u3::new(7));
+
+    // The number of variants is known, so exhaustiveness is checked as usual
+    #[bitenum(u1, exhaustive: true)]
+    #[derive(Eq, PartialEq, Debug)]
+    #[repr(u8)]
+    enum Exhaustive {
+        Low = BASE - 4,
+        High,
+    }
+
+    assert_eq!(Exhaustive::new_with_raw_value(u1::new(1)), Exhaustive::High);
+    assert_eq!(Exhaustive::Low.raw_value(), u1::new(0));
+}
+
+#[test]
+fn const_expression_values_with_fallback() {
+    const READ: u8 = 2;
+
+    #[bitenum(u3, exhaustive: false, fallback: Unknown)]
+    #[derive(Eq, PartialEq, Debug)]
+    enum Foo {
+        Read = READ,
+        Write,
+        Unknown(u3),
+        Erase = READ * 3,
+    }
+
+    assert_eq!(Foo::new_with_raw_value(u3::new(2)), Foo::Read);
+    assert_eq!(Foo::new_with_raw_value(u3::new(3)), Foo::Write);
+    assert_eq!(Foo::new_with_raw_value(u3::new(6)), Foo::Erase);
+    assert_eq!(
+        Foo::new_with_raw_value(u3::new(4)),
+        Foo::Unknown(u3::new(4))
+    );
+    assert_eq!(Foo::Write.raw_value(), u3::new(3));
+    assert_eq!(Foo::Erase.raw_value(), u3::new(6));
+}
@@ -1,4 +1,7 @@
-use arbitrary_int::{u13, u2, u3, u30, u4, u57};
+// The tests compare against literal bools and group binary literals by field rather than by nibble
+#![allow(clippy::bool_assert_comparison, clippy::unusual_byte_groupings)]
+
+use arbitrary_int::{u1, u12, u13, u19, u2, u3, u30, u4, u48, u5, u57, u7};
 use bitbybit::bitenum;
 use bitbybit::bitfield;
 
@@ -184,7 +187,7 @@ fn documentation() {
         field_a: u8,
 
         // A free standing comment - this should not become a doc comment
-        #[bits(8..=15, rw)]
+        #[bits(8..=15, rw, overlaps: field_a)]
         undocumented: u8,
 
         // Another free standing comment
@@ -544,6 +547,56 @@ fn bitfield_with_enum_nonexhaustive() {
     // able to write it
 }
 
+#[test]
+fn bitfield_with_enum_fallback() {
+    #[bitenum(u2, exhaustive: false, fallback: Unknown)]
+    #[derive(Eq, PartialEq, Debug)]
+    pub enum FallbackEnum {
+        Zero = 0b00,
+        One = 0b01,
+        Unknown(u2),
+    }
+
+    #[bitfield(u64, default: 0)]
+    pub struct BitfieldWithEnumFallback {
+        #[bits(2..=3, rw)]
+        e2: FallbackEnum,
+
+        #[bits(4..=5, rw, stride: 2)]
+        e2_array: [FallbackEnum; 2],
+    }
+
+    assert_eq!(
+        FallbackEnum::One,
+        BitfieldWithEnumFallback::new_with_raw_value(0b0110).e2()
+    );
+    assert_eq!(
+        FallbackEnum::Unknown(u2::new(3)),
+        BitfieldWithEnumFallback::new_with_raw_value(0b1110).e2()
+    );
+    assert_eq!(
+        FallbackEnum::Unknown(u2::new(2)),
+        BitfieldWithEnumFallback::new_with_raw_value(0b1000_0000).e2_array(1)
+    );
+
+    // Unknown values are written back unchanged
+    assert_eq!(
+        0b1100,
+        BitfieldWithEnumFallback::new()
+            .with_e2(FallbackEnum::Unknown(u2::new(3)))
+            .raw_value()
+    );
+    let value = BitfieldWithEnumFallback::new_with_raw_value(0b1011_1000);
+    assert_eq!(
+        0b1011_1000,
+        BitfieldWithEnumFallback::new()
+            .with_e2(value.e2())
+            .with_e2_array(0, value.e2_array(0))
+            .with_e2_array(1, value.e2_array(1))
+            .raw_value()
+    );
+}
+
 #[test]
 fn bitfield_with_indexed_exhaustive_enum() {
     #[bitenum(u2, exhaustive: true)]
@@ -729,3 +782,1175 @@ fn default_value_automatically_implements_default() {
     let b = Test::new();
     assert_eq!(a, b);
 }
+
+#[test]
+fn msb0_bit_order() {
+    #[bitfield(u32, bit_order: msb0, default: 0)]
+    struct Test {
+        #[bits(0..=3, rw)]
+        top_nibble: u4,
+
+        #[bits(4..=11, rw)]
+        byte: u8,
+
+        #[bit(31, rw)]
+        lowest_bit: bool,
+
+        #[bit(0, rw, overlaps: top_nibble)]
+        highest_bit: bool,
+    }
+
+    let t = Test::new_with_raw_value(0x9ABC_DEF1);
+    assert_eq!(u4::new(0x9), t.top_nibble());
+    assert_eq!(0xAB, t.byte());
+    assert!(t.lowest_bit());
+    assert!(t.highest_bit());
+
+    let t = Test::new()
+        .with_top_nibble(u4::new(0x5))
+        .with_byte(0x12)
+        .with_lowest_bit(true);
+    assert_eq!(0x5120_0001, t.raw_value());
+    assert!(!t.highest_bit());
+}
+
+#[test]
+fn msb0_bit_order_explicit_lsb0() {
+    #[bitfield(u8, bit_order: lsb0)]
+    struct Test {
+        #[bits(0..=3, rw)]
+        low_nibble: u4,
+    }
+
+    assert_eq!(u4::new(0x2), Test::new_with_raw_value(0x12).low_nibble());
+}
+
+#[test]
+fn msb0_repeated_bitrange() {
+    // Like condition register fields on PowerPC: element 0 occupies the most significant bits
+    #[bitfield(u32, bit_order: msb0, default: 0)]
+    struct ConditionRegister {
+        #[bits(0..=3, rw)]
+        field: [u4; 8],
+
+        #[bit(2, rw, stride: 4, overlaps: field)]
+        eq: [bool; 8],
+    }
+
+    let cr = ConditionRegister::new_with_raw_value(0x1234_5678);
+    assert_eq!(u4::new(0x1), cr.field(0));
+    assert_eq!(u4::new(0x2), cr.field(1));
+    assert_eq!(u4::new(0x8), cr.field(7));
+    assert!(!cr.eq(0));
+    assert!(cr.eq(1));
+    assert!(!cr.eq(7));
+
+    assert_eq!(0x1234_56F8, cr.with_field(6, u4::new(0xF)).raw_value());
+    assert_eq!(0x3234_5678, cr.with_eq(0, true).raw_value());
+}
+
+#[test]
+fn signed_arbitrary_width() {
+    #[bitfield(u32, default: 0)]
+    struct Accelerometer {
+        #[bits(0..=3, rw)]
+        status: u4,
+
+        #[bits(4..=15, rw)]
+        x: i12,
+
+        #[bits(16..=18, rw)]
+        small: i3,
+
+        #[bits(19..=26, rw)]
+        regular: i8,
+
+        #[bits(27..=31, rw)]
+        top: u5,
+    }
+
+    let a = Accelerometer::new_with_raw_value(0x0000_FFF5);
+    assert_eq!(-1, a.x());
+    assert_eq!(u4::new(0x5), a.status());
+
+    let a = Accelerometer::new_with_raw_value(0x0000_8000);
+    assert_eq!(-2048, a.x());
+    let a = Accelerometer::new_with_raw_value(0x0000_7FF0);
+    assert_eq!(2047, a.x());
+
+    // Setting negative values must not spill into the neighbouring fields
+    let a = Accelerometer::new()
+        .with_x(-2)
+        .with_small(-4)
+        .with_regular(-1);
+    assert_eq!(0x07FC_FFE0, a.raw_value());
+    assert_eq!(-2, a.x());
+    assert_eq!(-4, a.small());
+    assert_eq!(-1, a.regular());
+    assert_eq!(u4::new(0), a.status());
+    assert_eq!(u5::new(0), a.top());
+
+    // Values that don't fit are truncated
+    assert_eq!(3, Accelerometer::new().with_small(11).small());
+}
+
+#[test]
+fn signed_arbitrary_width_indexed() {
+    #[bitfield(u64, default: 0)]
+    struct Samples {
+        #[bits(0..=11, rw, stride: 16)]
+        sample: [i12; 4],
+    }
+
+    let s = Samples::new()
+        .with_sample(0, -5)
+        .with_sample(1, 100)
+        .with_sample(3, -2048);
+    assert_eq!(0x0800_0000_0064_0FFB, s.raw_value());
+    assert_eq!(-5, s.sample(0));
+    assert_eq!(100, s.sample(1));
+    assert_eq!(0, s.sample(2));
+    assert_eq!(-2048, s.sample(3));
+}
+
+#[test]
+fn split_field() {
+    // Immediate of a RISC-V B-type instruction: imm[12|10:5] are bits 31:25 and imm[4:1|11] are bits 11:7
+    #[bitfield(u32, default: 0)]
+    struct BType {
+        #[bits([31, 7, 30..=25, 11..=8], rw)]
+        imm: u12,
+
+        #[bits(0..=6, rw)]
+        opcode: u7,
+    }
+
+    let b = BType::new_with_raw_value(0xD400_0C63);
+    assert_eq!(u12::new(0b1_0_101010_1100), b.imm());
+    assert_eq!(u7::new(0x63), b.opcode());
+
+    let b = BType::new()
+        .with_opcode(u7::new(0x63))
+        .with_imm(u12::new(0b1_0_101010_1100));
+    assert_eq!(0xD400_0C63, b.raw_value());
+    assert_eq!(
+        0xD400_0CE3,
+        b.with_imm(u12::new(0b1_1_101010_1100)).raw_value()
+    );
+    assert_eq!(0x0000_0063, b.with_imm(u12::new(0)).raw_value());
+}
+
+#[test]
+fn split_field_regular_and_signed() {
+    #[bitfield(u16, default: 0)]
+    struct Test {
+        #[bits([15..=12, 3..=0], rw)]
+        byte: u8,
+
+        #[bits([11..=10, 5..=4], rw)]
+        signed: i4,
+    }
+
+    let t = Test::new_with_raw_value(0xA00B);
+    assert_eq!(0xAB, t.byte());
+    assert_eq!(
+        0x1834,
+        Test::new().with_byte(0x14).with_signed(-5).raw_value()
+    );
+    assert_eq!(-5, Test::new().with_signed(-5).signed());
+    assert_eq!(0x0830, Test::new().with_signed(-5).raw_value());
+}
+
+#[test]
+fn byte_array_base() {
+    #[bitfield([u8; 20], default: [0; 20])]
+    struct Header {
+        #[bits(0..=3, rw)]
+        low_nibble: u4,
+
+        // Straddles the first two bytes
+        #[bits(4..=11, rw)]
+        straddling: u8,
+
+        #[bit(12, rw)]
+        flag: bool,
+
+        #[bits(13..=31, rw)]
+        wide: u19,
+
+        #[bits(32..=159, rw)]
+        huge: u128,
+
+        #[bits(156..=159, rw, overlaps: huge)]
+        nibble_in_huge: u4,
+    }
+
+    let mut raw = [0u8; 20];
+    raw[0] = 0x21;
+    raw[1] = 0x13;
+    raw[5] = 0xEF;
+    raw[19] = 0xA0;
+    let h = Header::new_with_raw_value(raw);
+    assert_eq!(u4::new(0x1), h.low_nibble());
+    assert_eq!(0x32, h.straddling());
+    assert!(h.flag());
+    assert_eq!(0xA000_0000_0000_0000_0000_0000_0000_EF00, h.huge());
+    assert_eq!(u4::new(0xA), h.nibble_in_huge());
+
+    let h = Header::new()
+        .with_straddling(0xAB)
+        .with_wide(u19::new(0x7_FFFF))
+        .with_huge(0x1234_5678_9ABC_DEF0_0FED_CBA9_8765_4321);
+    assert_eq!(0xAB, h.straddling());
+    assert_eq!(u19::new(0x7_FFFF), h.wide());
+    assert_eq!(0x1234_5678_9ABC_DEF0_0FED_CBA9_8765_4321, h.huge());
+    assert_eq!(
+        [
+            0xB0, 0xEA, 0xFF, 0xFF, 0x21, 0x43, 0x65, 0x87, 0xA9, 0xCB, 0xED, 0x0F, 0xF0, 0xDE,
+            0xBC, 0x9A, 0x78, 0x56, 0x34, 0x12
+        ],
+        h.raw_value()
+    );
+    assert_eq!(u4::new(0x1), h.nibble_in_huge());
+    assert_eq!(
+        0x0234_5678_9ABC_DEF0_0FED_CBA9_8765_4321,
+        h.with_nibble_in_huge(u4::new(0)).huge()
+    );
+    assert!(!h.with_flag(false).flag());
+
+    // Everything is usable in const contexts
+    const FLAGGED: Header = Header::new().with_flag(true).with_straddling(0x12);
+    const STRADDLING: u8 = FLAGGED.straddling();
+    assert_eq!(0x12, STRADDLING);
+    assert!(FLAGGED.flag());
+    assert_eq!(0xEA & !0x10, h.with_flag(false).raw_value()[1]);
+}
+
+#[test]
+fn word_array_base_with_indexed_and_split_fields() {
+    #[bitfield([u32; 6], default: [0; 6])]
+    struct Descriptor {
+        // Elements straddle the boundaries between the words
+        #[bits(28..=39, rw, stride: 32)]
+        value: [u12; 5],
+
+        #[bits([191..=188, 3..=0], rw)]
+        split: u8,
+    }
+
+    let d = Descriptor::new()
+        .with_value(0, u12::new(0xABC))
+        .with_value(4, u12::new(0x123))
+        .with_split(0x5A);
+    assert_eq!(
+        [0xC000_000A, 0x0000_00AB, 0, 0, 0x3000_0000, 0x5000_0012],
+        d.raw_value()
+    );
+    assert_eq!(u12::new(0xABC), d.value(0));
+    assert_eq!(u12::new(0), d.value(1));
+    assert_eq!(u12::new(0x123), d.value(4));
+    assert_eq!(0x5A, d.split());
+}
+
+#[test]
+fn byte_conversions() {
+    #[bitfield(u32)]
+    struct Test {
+        #[bits(0..=7, rw)]
+        low: u8,
+    }
+
+    let t = Test::new_with_raw_value(0x1234_5678);
+    assert_eq!([0x78, 0x56, 0x34, 0x12], t.to_le_bytes());
+    assert_eq!([0x12, 0x34, 0x56, 0x78], t.to_be_bytes());
+    assert_eq!(
+        0x1234_5678,
+        Test::from_le_bytes([0x78, 0x56, 0x34, 0x12]).raw_value()
+    );
+    assert_eq!(
+        0x1234_5678,
+        Test::from_be_bytes([0x12, 0x34, 0x56, 0x78]).raw_value()
+    );
+
+    const FROM_BYTES: Test = Test::from_be_bytes([0x12, 0x34, 0x56, 0x78]);
+    assert_eq!(0x78, FROM_BYTES.low());
+
+    // Slices may be longer than the bitfield, but not shorter
+    let packet = [0x12, 0x34, 0x56, 0x78, 0x9A];
+    assert_eq!(
+        0x1234_5678,
+        Test::from_be_slice(&packet).unwrap().raw_value()
+    );
+    assert_eq!(
+        0x7856_3412,
+        Test::from_le_slice(&packet).unwrap().raw_value()
+    );
+    assert_eq!(
+        u32::from_ne_bytes([0x12, 0x34, 0x56, 0x78]),
+        Test::from_bytes(&packet).unwrap().raw_value()
+    );
+    assert_eq!(
+        Some(TestFromBytesError { length: 3 }),
+        Test::from_be_slice(&packet[..3]).err()
+    );
+    assert_eq!(
+        Some(0),
+        Test::from_le_slice(&[]).err().map(|error| error.length)
+    );
+    assert_eq!(
+        "slice is too short for Test, which needs 4 bytes (2 bytes)",
+        Test::from_bytes(&packet[..2]).err().unwrap().to_string()
+    );
+}
+
+#[test]
+fn byte_conversions_array_base() {
+    #[bitfield([u16; 3])]
+    struct Test {
+        #[bits(0..=7, rw)]
+        low: u8,
+    }
+
+    let t = Test::new_with_raw_value([0x3412, 0x7856, 0xBC9A]);
+    assert_eq!([0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC], t.to_le_bytes());
+    assert_eq!([0xBC, 0x9A, 0x78, 0x56, 0x34, 0x12], t.to_be_bytes());
+    assert_eq!(
+        [0x3412, 0x7856, 0xBC9A],
+        Test::from_le_bytes([0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC]).raw_value()
+    );
+    assert_eq!(
+        [0x3412, 0x7856, 0xBC9A],
+        Test::from_be_bytes([0xBC, 0x9A, 0x78, 0x56, 0x34, 0x12]).raw_value()
+    );
+    assert_eq!(
+        0x34,
+        Test::from_be_slice(&[0xBC, 0x9A, 0x78, 0x56, 0x12, 0x34])
+            .unwrap()
+            .low()
+    );
+}
+
+#[test]
+fn nested_bitfield() {
+    #[bitfield(u8, default: 0)]
+    struct Attributes {
+        #[bit(0, rw)]
+        dirty: bool,
+
+        #[bit(1, rw)]
+        accessed: bool,
+
+        #[bits(4..=7, rw)]
+        level: u4,
+    }
+
+    #[bitfield(u5, default: 0b1_0001)]
+    struct Permissions {
+        #[bit(4, rw)]
+        read: bool,
+
+        #[bits(0..=3, rw)]
+        key: u4,
+    }
+
+    #[bitfield(u64, default: 0)]
+    struct PageTableEntry {
+        #[bits(16..=63, rw)]
+        address: u48,
+
+        #[bits(8..=15, rw)]
+        attrs: Attributes,
+
+        #[bits(0..=4, rw)]
+        permissions: Permissions,
+    }
+
+    let entry = PageTableEntry::new_with_raw_value(0x1234_A113);
+    assert_eq!(0xA1, entry.attrs().raw_value());
+    assert_eq!(true, entry.attrs().dirty());
+    assert_eq!(false, entry.attrs().accessed());
+    assert_eq!(u4::new(0xA), entry.attrs().level());
+    assert_eq!(u5::new(0x13), entry.permissions().raw_value());
+    assert_eq!(true, entry.permissions().read());
+    assert_eq!(u4::new(0x3), entry.permissions().key());
+
+    let entry = entry.with_attrs(entry.attrs().with_dirty(false).with_accessed(true));
+    assert_eq!(0x1234_A213, entry.raw_value());
+
+    let entry = entry.with_permissions(entry.permissions().with_read(false));
+    assert_eq!(0x1234_A203, entry.raw_value());
+
+    const DEFAULT_PERMISSIONS: Permissions = Permissions::new();
+    assert_eq!(u5::new(0b1_0001), DEFAULT_PERMISSIONS.raw_value());
+    assert_eq!(
+        0x1F,
+        PageTableEntry::new()
+            .with_permissions(Permissions::new_with_raw_value(u5::new(0x1F)))
+            .raw_value()
+    );
+}
+
+#[test]
+fn generated_debug() {
+    #[bitenum(u2, exhaustive: false)]
+    #[derive(Debug)]
+    enum Mode {
+        Off = 0,
+        On = 1,
+        Blinking = 2,
+    }
+
+    #[bitfield(u8, debug)]
+    struct Inner {
+        #[bits(0..=7, r)]
+        value: u8,
+    }
+
+    #[bitfield(u32, default: 0, debug)]
+    struct Test {
+        #[bits(28..=31, w)]
+        command: u4,
+
+        #[bits(24..=27, rw)]
+        nibble: u4,
+
+        #[bits(16..=23, r)]
+        inner: Inner,
+
+        #[bits(8..=9, rw)]
+        mode: Option<Mode>,
+
+        #[bits(10..=11, rw)]
+        other_mode: Option<Mode>,
+
+        #[bit(0, rw)]
+        flag: [bool; 4],
+    }
+
+    let t = Test::new_with_raw_value(0xF5AB_0E05);
+    assert_eq!(
+        "Test { nibble: 5, inner: Inner { value: 171 }, mode: Ok(Blinking), other_mode: Err(3), flag: [true, false, true, false] }",
+        format!("{:?}", t)
+    );
+}
+
+#[test]
+fn builder() {
+    #[bitenum(u2, exhaustive: true)]
+    #[derive(Eq, PartialEq, Debug)]
+    enum Mode {
+        Zero = 0,
+        One = 1,
+        Two = 2,
+        Three = 3,
+    }
+
+    #[bitfield(u32, builder)]
+    struct Test {
+        #[bits(24..=31, r)]
+        status: u8,
+
+        #[bits(16..=23, w)]
+        command: u8,
+
+        #[bits(8..=9, rw)]
+        mode: Mode,
+
+        #[bits(0..=1, rw)]
+        nibble: [u2; 2],
+    }
+
+    // Usable in const contexts and in any order
+    const T: Test = Test::builder()
+        .with_nibble([u2::new(1), u2::new(3)])
+        .with_command(0x12)
+        .with_mode(Mode::Two)
+        .build();
+    assert_eq!(0x0012_020D, T.raw_value());
+    assert_eq!(Mode::Two, T.mode());
+
+    // Without a default, read-only fields are 0. With a default, they keep the default
+    #[bitfield(u16, default: 0xAB00, builder)]
+    struct WithDefault {
+        #[bits(8..=15, r)]
+        status: u8,
+
+        #[bits(0..=7, rw)]
+        value: u8,
+    }
+    let t = WithDefault::builder().with_value(0xCD).build();
+    assert_eq!(0xABCD, t.raw_value());
+
+    // Array base data types are supported as well
+    #[bitfield([u8; 3], builder)]
+    struct ArrayBased {
+        #[bits(4..=19, rw)]
+        value: u16,
+    }
+    let t = ArrayBased::builder().with_value(0xABCD).build();
+    assert_eq!([0xD0, 0xBC, 0x0A], t.raw_value());
+}
+
+#[test]
+fn builder_with_overlapping_fields() {
+    #[bitfield(u16, builder)]
+    struct Test {
+        #[bits(8..=15, rw, overlaps: high_nibble)]
+        high: u8,
+
+        #[bits(12..=15, rw)]
+        high_nibble: u4,
+
+        #[bits(0..=7, rw)]
+        low: u8,
+    }
+
+    // Overlapping fields are optional and can be set in any order
+    let t = Test::builder().with_low(0x12).build();
+    assert_eq!(0x0012, t.raw_value());
+    let t = Test::builder()
+        .with_high(0xAB)
+        .with_low(0x12)
+        .with_high_nibble(u4::new(0xC))
+        .build();
+    assert_eq!(0xCB12, t.raw_value());
+}
+
+#[test]
+fn builder_is_opt_in() {
+    // Without 'builder', names like ControlBuilder and builder are available
+    #[allow(dead_code)]
+    struct ControlBuilder;
+
+    #[bitfield(u8, default: 0)]
+    struct Control {
+        #[bits(0..=7, rw)]
+        builder: u8,
+    }
+
+    assert_eq!(0x12, Control::new().with_builder(0x12).builder());
+}
+
+#[test]
+fn register() {
+    #[bitfield(u32, register)]
+    struct Control {
+        #[bits(16..=31, r)]
+        status: u16,
+
+        #[bits(8..=15, w)]
+        command: u8,
+
+        #[bits(0..=7, rw)]
+        value: u8,
+    }
+
+    let mut memory = 0x1234_5678u32;
+    let register = unsafe { ControlRegister::new(&mut memory as *mut u32 as *mut Control) };
+    assert_eq!(0x1234, register.read().status());
+    assert_eq!(0x78, register.read().value());
+
+    register.modify(|r| r.with_value(0xAB).with_command(0xCD));
+    assert_eq!(0x1234_CDAB, register.read().raw_value());
+
+    register.write(Control::new_with_raw_value(0x1111_2222));
+    assert_eq!(0x1111_2222, register.read().raw_value());
+    assert_eq!(&mut memory as *mut u32 as *mut Control, register.as_ptr());
+    assert_eq!(0x1111_2222, memory);
+
+    // Write-only registers can't be read, so they only provide write()
+    #[bitfield(u8, register, builder)]
+    struct Command {
+        #[bits(0..=7, w)]
+        command: u8,
+    }
+
+    let mut memory = 0u8;
+    let register = unsafe { CommandRegister::new(&mut memory as *mut u8 as *mut Command) };
+    register.write(Command::builder().with_command(0x12).build());
+    assert_eq!(0x12, memory);
+}
+
+#[test]
+fn reserved_bits() {
+    #[bitfield(u32, default: 0xFFFF_FFFF, builder)]
+    struct Test {
+        #[bits(16..=31, reserved: 0)]
+        _reserved0: u16,
+
+        #[bits(8..=15, rw)]
+        value: u8,
+
+        #[bits(4..=7, reserved: 0b1010)]
+        _reserved1: u4,
+
+        #[bits(0..=3, rw)]
+        nibble: u4,
+    }
+
+    // new() and the builder force the reserved values, setters keep them
+    assert_eq!(0x0000_FFAF, Test::new().raw_value());
+    assert_eq!(
+        0x0000_12A3,
+        Test::new()
+            .with_value(0x12)
+            .with_nibble(u4::new(3))
+            .raw_value()
+    );
+    assert_eq!(
+        0x0000_12A3,
+        Test::builder()
+            .with_value(0x12)
+            .with_nibble(u4::new(3))
+            .build()
+            .raw_value()
+    );
+
+    // try_new_with_raw_value() returns the reserved bits that are wrong
+    assert_eq!(
+        0x0000_12A3,
+        Test::try_new_with_raw_value(0x0000_12A3)
+            .unwrap()
+            .raw_value()
+    );
+    assert_eq!(
+        Some(0x8000_0000),
+        Test::try_new_with_raw_value(0x8000_12A3).err()
+    );
+    assert_eq!(
+        Some(0x0001_00F0),
+        Test::try_new_with_raw_value(0x0001_1253).err()
+    );
+
+    // new_with_raw_value() still doesn't check anything
+    assert_eq!(
+        0xFFFF_FFFF,
+        Test::new_with_raw_value(0xFFFF_FFFF).raw_value()
+    );
+}
+
+#[test]
+fn reserved_bits_array_base() {
+    #[bitfield([u8; 3], builder)]
+    struct Test {
+        #[bits(12..=23, reserved: 0xFFF)]
+        _reserved0: u12,
+
+        #[bits(4..=11, rw)]
+        value: u8,
+
+        #[bit(0, reserved: 1, stride: 2)]
+        _reserved1: [bool; 2],
+    }
+
+    assert_eq!(
+        [0x55, 0xF1, 0xFF],
+        Test::builder().with_value(0x15).build().raw_value()
+    );
+    assert!(Test::try_new_with_raw_value([0x05, 0xF0, 0xFF]).is_ok());
+    assert_eq!(
+        Some([0x04, 0x00, 0x80]),
+        Test::try_new_with_raw_value([0x01, 0xF0, 0x7F]).err()
+    );
+}
+
+#[test]
+fn write_side_effects() {
+    #[bitfield(u32, register)]
+    struct InterruptStatus {
+        #[bits(24..=27, w0c)]
+        errors: u4,
+
+        #[bit(16, w1s)]
+        start: bool,
+
+        #[bit(8, w1c)]
+        overflow: bool,
+
+        #[bit(0, w1c, stride: 2)]
+        pending: [bool; 4],
+
+        #[bit(31, rw)]
+        enable: bool,
+    }
+
+    let status = InterruptStatus::new_with_raw_value(0x8A01_0155);
+    assert!(status.pending(2));
+    assert!(status.overflow());
+    assert!(status.start());
+    assert_eq!(u4::new(0xA), status.errors());
+
+    // Only the given field triggers its side effect. Other fields are unchanged
+    assert_eq!(0x8F00_0010, status.clear_pending(2).raw_value());
+    assert_eq!(0x8F00_0100, status.clear_overflow().raw_value());
+    assert_eq!(0x8F01_0000, status.trigger_start().raw_value());
+    assert_eq!(0x8000_0000, status.clear_errors().raw_value());
+    assert_eq!(0x8F00_0000, status.without_side_effects().raw_value());
+
+    // modify() doesn't clear pending bits by accident
+    let mut memory = 0x0A01_0155u32;
+    let register =
+        unsafe { InterruptStatusRegister::new(&mut memory as *mut u32 as *mut InterruptStatus) };
+    register.modify(|r| r.with_enable(true));
+    assert_eq!(0x8F00_0000, memory);
+    register.write(register.read().clear_pending(0));
+    assert_eq!(0x8F00_0001, memory);
+}
+
+#[test]
+fn builder_with_write_side_effects() {
+    #[bitfield(u32, builder)]
+    struct InterruptControl {
+        #[bits(24..=27, w0c)]
+        errors: u4,
+
+        #[bit(8, w1c)]
+        overflow: bool,
+
+        #[bits(0..=7, rw)]
+        value: u8,
+    }
+
+    // w0c fields are 1 and w1c fields are 0, so writing the built value doesn't clear anything
+    assert_eq!(
+        0x0F00_0012,
+        InterruptControl::builder()
+            .with_value(0x12)
+            .build()
+            .raw_value()
+    );
+}
+
+#[test]
+fn read_clear() {
+    #[bitfield(u16, register, builder)]
+    struct Data {
+        #[bits(8..=15, rc)]
+        received: u8,
+
+        #[bits(0..=7, w)]
+        transmit: u8,
+    }
+
+    // Registers with read-to-clear fields have no modify(), but they can still be read and written
+    let mut memory = 0x1200u16;
+    let register = unsafe { DataRegister::new(&mut memory as *mut u16 as *mut Data) };
+    assert_eq!(0x12, register.read().received());
+    register.write(Data::builder().with_transmit(0x34).build());
+    assert_eq!(0x0034, memory);
+}
+
+#[test]
+fn in_place_setters() {
+    #[bitfield(u32, default: 0, in_place_setters)]
+    struct Test {
+        #[bits(16..=31, rw)]
+        value: u16,
+
+        #[bits(0..=3, rw)]
+        nibble: [u4; 4],
+    }
+
+    let mut t = Test::new();
+    t.set_value(0x1234);
+    for i in 0..4 {
+        t.set_nibble(i, u4::new(i as u8 + 1));
+    }
+    assert_eq!(0x1234_4321, t.raw_value());
+
+    // Usable in const contexts
+    const T: Test = {
+        let mut t = Test::new();
+        t.set_nibble(3, u4::new(0xF));
+        t.set_value(0xABCD);
+        t
+    };
+    assert_eq!(0xABCD_F000, T.raw_value());
+}
+
+#[test]
+fn mask_and_shift_constants() {
+    #[bitfield(u32)]
+    struct Test {
+        #[bits(11..=15, r)]
+        lspi: u5,
+
+        #[bit(10, rw)]
+        security_extn: bool,
+
+        #[bits([9, 5..=4], rw)]
+        split: u3,
+
+        #[bits(16..=19, rw, stride: 8)]
+        nibble: [u4; 2],
+    }
+
+    assert_eq!(0x0000_F800, Test::LSPI_MASK);
+    assert_eq!(11, Test::LSPI_SHIFT);
+    assert_eq!(5, Test::LSPI_WIDTH);
+    assert_eq!(0x0000_0400, Test::SECURITY_EXTN_MASK);
+    assert_eq!(10, Test::SECURITY_EXTN_SHIFT);
+    assert_eq!(1, Test::SECURITY_EXTN_WIDTH);
+    assert_eq!(0x0000_0230, Test::SPLIT_MASK);
+    assert_eq!(3, Test::SPLIT_WIDTH);
+    assert_eq!(4, Test::NIBBLE_WIDTH);
+    assert_eq!(8, Test::NIBBLE_STRIDE);
+    assert_eq!(0x000F_0000, Test::nibble_mask(0));
+    assert_eq!(0x0F00_0000, Test::nibble_mask(1));
+    assert_eq!(16, Test::nibble_shift(0));
+    assert_eq!(24, Test::nibble_shift(1));
+
+    // The masks match the accessors
+    let t = Test::new_with_raw_value(0xFFFF_FFFF);
+    assert_eq!(
+        (t.raw_value() & Test::LSPI_MASK) >> Test::LSPI_SHIFT,
+        t.lspi().value() as u32
+    );
+    assert_eq!(
+        Test::SPLIT_MASK,
+        Test::new_with_raw_value(0)
+            .with_split(u3::new(0b111))
+            .raw_value()
+    );
+
+    #[bitfield(u16, bit_order: msb0)]
+    struct Msb0 {
+        #[bits(0..=3, rw)]
+        top: u4,
+    }
+    assert_eq!(0xF000, Msb0::TOP_MASK);
+    assert_eq!(12, Msb0::TOP_SHIFT);
+
+    #[bitfield([u8; 3])]
+    struct ArrayBased {
+        #[bits(4..=11, rw)]
+        straddling: u8,
+
+        #[bits(12..=15, rw)]
+        nibble: [u4; 3],
+    }
+    assert_eq!([0xF0, 0x0F, 0x00], ArrayBased::STRADDLING_MASK);
+    assert_eq!([0x00, 0x00, 0xF0], ArrayBased::nibble_mask(2));
+
+    // Masks have the type of raw_value(), even if the base data type is an arbitrary int
+    #[bitfield(u5)]
+    struct ArbitraryBase {
+        #[bits(1..=3, rw)]
+        key: u3,
+
+        #[bit(0, rw, stride: 4)]
+        flag: [bool; 2],
+    }
+    let p = ArbitraryBase::new_with_raw_value(u5::new(0b1_1010));
+    assert_eq!(u5::new(0b0_1110), ArbitraryBase::KEY_MASK);
+    assert_eq!(u5::new(0b0_1010), p.raw_value() & ArbitraryBase::KEY_MASK);
+    assert_eq!(u5::new(0b1_0000), ArbitraryBase::flag_mask(1));
+}
+
+#[test]
+fn flags() {
+    #[bitfield(u8, default: 0, flags)]
+    #[derive(Eq, PartialEq, Debug)]
+    struct Irq {
+        #[bits(6..=7, reserved: 0b10)]
+        _reserved: u2,
+
+        #[bits(4..=5, rw)]
+        priority: u2,
+
+        #[bit(2, rw)]
+        uart: bool,
+
+        #[bit(1, rw)]
+        spi: bool,
+
+        #[bit(0, rw)]
+        timer: bool,
+    }
+
+    const ENABLED: Irq = Irq::TIMER;
+    assert_eq!(0x81, ENABLED.raw_value());
+    assert_eq!(0x85, (Irq::TIMER | Irq::UART).raw_value());
+    assert_eq!(Irq::TIMER, (Irq::TIMER | Irq::UART) & Irq::TIMER);
+    assert_eq!(
+        Irq::UART,
+        (Irq::TIMER | Irq::UART) ^ Irq::TIMER ^ Irq::new()
+    );
+
+    // Not doesn't change reserved bits
+    assert_eq!(0xBE, (!Irq::TIMER).raw_value());
+
+    let mut irq = Irq::SPI;
+    irq |= Irq::TIMER;
+    assert!(irq.spi() && irq.timer() && !irq.uart());
+    irq &= !Irq::SPI;
+    assert_eq!(Irq::TIMER, irq);
+    irq ^= Irq::TIMER.with_priority(u2::new(3));
+    assert_eq!(0xB0, irq.raw_value());
+
+    // Arbitrary int and array base data types
+    #[bitfield(u5, flags)]
+    struct Small {
+        #[bit(4, rw)]
+        top: bool,
+    }
+    assert_eq!(u5::new(0x0F), (!Small::TOP).raw_value());
+
+    #[bitfield([u8; 2], flags)]
+    struct Wide {
+        #[bit(12, rw)]
+        high: bool,
+
+        #[bit(3, rw)]
+        low: bool,
+    }
+    assert_eq!([0x08, 0x10], (Wide::HIGH | Wide::LOW).raw_value());
+    assert_eq!([0xF7, 0xFF], (!Wide::LOW).raw_value());
+}
+
+#[test]
+fn whole_array_accessors() {
+    #[bitenum(u2, exhaustive: false)]
+    #[derive(Eq, PartialEq, Debug)]
+    enum Mode {
+        Off = 0,
+        On = 1,
+        Blinking = 2,
+    }
+
+    #[bitfield(u32, default: 0, in_place_setters)]
+    #[derive(Eq, PartialEq, Debug)]
+    struct Test {
+        #[bits(28..=29, r)]
+        state: [u2; 2],
+
+        #[bits(16..=19, rw, stride: 8)]
+        nibble: [u4; 2],
+
+        #[bits(8..=9, rw)]
+        mode: [Option<Mode>; 3],
+
+        #[bit(0, rw)]
+        flag: [bool; 8],
+    }
+
+    assert_eq!(2, Test::state_count());
+    assert_eq!(2, Test::nibble_count());
+    assert_eq!(3, Test::mode_count());
+    assert_eq!(8, Test::flag_count());
+
+    let t = Test::new_with_raw_value(0x3005_0E0F);
+    assert_eq!([u2::new(3), u2::new(0)], t.state_array());
+    assert_eq!([u4::new(0x5), u4::new(0x0)], t.nibble_array());
+    assert_eq!([Ok(Mode::Blinking), Err(3), Ok(Mode::Off)], t.mode_array());
+    assert_eq!(
+        [true, true, true, true, false, false, false, false],
+        t.flag_array()
+    );
+
+    // Setting the whole array is the same as setting every element
+    let values = [u4::new(0x7), u4::new(0xC)];
+    assert_eq!(
+        Test::new()
+            .with_nibble(0, values[0])
+            .with_nibble(1, values[1]),
+        Test::new().with_nibble_array(values)
+    );
+    assert_eq!(
+        0x0C07_0000,
+        Test::new().with_nibble_array(values).raw_value()
+    );
+    assert_eq!(
+        t,
+        Test::new_with_raw_value(0x3005_0E00).with_flag_array(t.flag_array())
+    );
+
+    const T: Test = {
+        let mut t = Test::new();
+        t.set_mode_array([Mode::Blinking, Mode::Off, Mode::On]);
+        t
+    };
+    assert_eq!(0x0000_1200, T.raw_value());
+}
+
+#[test]
+fn bounded_index() {
+    #[bitfield(u64, default: 0, debug, in_place_setters)]
+    struct Test {
+        #[bits(0..=3, rw, index: u4)]
+        nibble: [u4; 16],
+    }
+
+    let t = Test::new_with_raw_value(0x12345678_ABCDEF00);
+    assert_eq!(u4::new(0xE), t.nibble(u4::new(3)));
+    assert_eq!(u4::new(0x1), t.nibble(u4::new(15)));
+    assert_eq!(
+        0x92345678_ABCDEF00,
+        t.with_nibble(u4::new(15), u4::new(0x9)).raw_value()
+    );
+    assert_eq!(t.nibble(u4::new(4)), t.nibble_array()[4]);
+
+    let mut t = Test::new();
+    t.set_nibble(u4::new(1), u4::new(0xF));
+    assert_eq!(0xF0, t.raw_value());
+
+    #[bitfield(u8, default: 0)]
+    struct Flags {
+        #[bit(0, rw, index: u1)]
+        flag: [bool; 2],
+
+        #[bit(4, rw, index: u2)]
+        other: [bool; 4],
+    }
+    let f = Flags::new()
+        .with_flag(u1::new(1), true)
+        .with_other(u2::new(3), true);
+    assert_eq!(0b1000_0010, f.raw_value());
+    assert!(f.flag(u1::new(1)));
+    assert_eq!([false, false, false, true], f.other_array());
+}
+
+#[test]
+fn try_accessors() {
+    #[bitfield(u32, default: 0)]
+    struct Test {
+        #[bits(0..=3, rw, stride: 8)]
+        nibble: [u4; 3],
+    }
+
+    let t = Test::new_with_raw_value(0x0C0B_0A09);
+    assert_eq!(Some(u4::new(0xA)), t.try_nibble(1));
+    assert_eq!(Some(u4::new(0xB)), t.try_nibble(2));
+    assert_eq!(None, t.try_nibble(3));
+    assert_eq!(None, t.try_nibble(usize::MAX));
+    assert_eq!(
+        Some(0x0C0B_0A01),
+        t.try_with_nibble(0, u4::new(1)).map(|t| t.raw_value())
+    );
+    assert!(t.try_with_nibble(3, u4::new(1)).is_none());
+}
+
+#[test]
+fn field_defaults() {
+    #[bitenum(u2, exhaustive: false)]
+    #[derive(Eq, PartialEq, Debug)]
+    enum Mode {
+        Slow = 0,
+        Fast = 1,
+    }
+
+    #[bitfield(u32)]
+    #[derive(Eq, PartialEq, Debug)]
+    struct Test {
+        #[bits(28..=31, reserved: 0b1010)]
+        _reserved: u4,
+
+        #[bits(16..=23, r, default: 0x12)]
+        id: u8,
+
+        #[bits(12..=13, rw, default: Mode::Fast)]
+        mode: Option<Mode>,
+
+        #[bits(4..=7, rw, default: 5)]
+        nibble: u4,
+
+        #[bit(3, rw, default: true)]
+        enabled: bool,
+
+        #[bit(2, rw)]
+        no_default: bool,
+
+        #[bit(0, rw, default: [true, false])]
+        flag: [bool; 2],
+    }
+
+    const T: Test = Test::new();
+    assert_eq!(0xA012_1059, T.raw_value());
+    assert_eq!(Test::new(), Test::default());
+    assert_eq!(0x12, T.id());
+    assert_eq!(Ok(Mode::Fast), T.mode());
+    assert_eq!(u4::new(5), T.nibble());
+    assert!(T.enabled() && !T.no_default());
+    assert_eq!([true, false], T.flag_array());
+
+    // A single value is used for every element of an array
+    #[bitfield([u8; 2])]
+    struct ArrayBased {
+        #[bits(4..=11, rw, default: 0xAB)]
+        straddling: u8,
+
+        #[bits(0..=1, rw, default: 2)]
+        small: [u2; 2],
+
+        #[bits(12..=13, rw, default: [3; 2])]
+        repeated: [u2; 2],
+    }
+    assert_eq!([0xBA, 0xFA], ArrayBased::new().raw_value());
+}
+
+#[test]
+fn raw_identifier_fields() {
+    #[bitfield(u16, default: 0, debug, in_place_setters, flags, builder)]
+    struct Test {
+        #[bits(8..=15, rw)]
+        r#type: u8,
+
+        #[bit(4, w1c)]
+        r#match: bool,
+
+        #[bit(0, rw)]
+        r#loop: [bool; 2],
+    }
+
+    let mut t = Test::new().with_type(0x12).with_loop_array([true, false]);
+    t.set_loop(1, true);
+    assert_eq!(0x12, t.r#type());
+    assert_eq!([true, true], t.loop_array());
+    assert_eq!(2, Test::loop_count());
+    assert_eq!(0x0010, t.clear_match().raw_value() & Test::MATCH_MASK);
+    assert_eq!(0x0010, Test::MATCH.raw_value());
+    assert_eq!(0xFF00, Test::TYPE_MASK);
+    assert_eq!(
+        0x1203,
+        Test::builder()
+            .with_type(0x12)
+            .with_loop([true, true])
+            .build()
+            .raw_value()
+    );
+    assert_eq!(
+        "Test { type: 18, match: false, loop: [true, true] }",
+        format!("{:?}", t)
+    );
+}
+
+#[test]
+fn fields_named_like_internal_helpers() {
+    #[bitfield(u16, default: 0)]
+    struct Test {
+        #[bits(12..=15, reserved: 0b1010)]
+        _reserved: u4,
+
+        #[bit(8, w1c)]
+        pending: bool,
+
+        #[bits(0..=3, rw)]
+        reserved_bits: u4,
+
+        #[bits(4..=7, rw)]
+        side_effect_bits: u4,
+    }
+
+    let t = Test::new()
+        .with_reserved_bits(u4::new(0x3))
+        .with_side_effect_bits(u4::new(0x5));
+    assert_eq!(0xA053, t.raw_value());
+    assert_eq!(0x000F, Test::RESERVED_BITS_MASK);
+    assert_eq!(0x00F0, Test::SIDE_EFFECT_BITS_MASK);
+    assert_eq!(
+        0xA053,
+        Test::new_with_raw_value(0xA153)
+            .without_side_effects()
+            .raw_value()
+    );
+}
@@ -0,0 +1,73 @@
+use arbitrary_int::{u2, u4};
+use bitbybit::{bitenum, bitfield};
+
+// Formatting requires a global logger and the defmt linker script, so these tests only ensure that
+// the implementations exist and compile
+fn assert_format<T: defmt::Format>(_value: T) {}
+
+#[bitenum(u2, exhaustive: false, defmt)]
+enum Mode {
+    Off = 0,
+    On = 1,
+    Blinking = 2,
+}
+
+#[bitenum(u1, exhaustive: true, defmt)]
+enum Direction {
+    In = 0,
+    Out = 1,
+}
+
+#[bitenum(u3, exhaustive: false, fallback: Unknown, defmt)]
+enum Command {
+    Read = 0,
+    Unknown(arbitrary_int::u3),
+}
+
+#[test]
+fn bitenum() {
+    assert_format(Mode::Blinking);
+    assert_format(Mode::new_with_raw_value(u2::new(3)));
+    assert_format(Direction::new_with_raw_value(arbitrary_int::u1::new(1)));
+    assert_format(Command::new_with_raw_value(arbitrary_int::u3::new(5)));
+}
+
+#[test]
+fn bitfield() {
+    #[bitfield(u8, default: 0, defmt)]
+    struct Nested {
+        #[bit(0, rw)]
+        enabled: bool,
+    }
+
+    #[bitfield(u32, default: 0, defmt)]
+    struct Test {
+        #[bits(24..=31, rw)]
+        nested: Nested,
+
+        #[bits(16..=23, w)]
+        command: u8,
+
+        #[bits(12..=13, rw)]
+        mode: Option<Mode>,
+
+        #[bit(11, rw)]
+        direction: Direction,
+
+        #[bits(4..=7, rw)]
+        nibble: u4,
+
+        #[bit(0, rw)]
+        flag: [bool; 4],
+    }
+
+    assert_format(Test::new().with_mode(Mode::On));
+
+    #[bitfield(u16, defmt)]
+    struct WriteOnly {
+        #[bits(0..=15, w)]
+        value: u16,
+    }
+
+    assert_format(WriteOnly::new_with_raw_value(0x1234));
+}
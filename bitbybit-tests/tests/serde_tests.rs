@@ -0,0 +1,273 @@
+use arbitrary_int::{u2, u3, u4, u5};
+use bitbybit::{bitenum, bitfield};
+
+#[bitenum(u2, exhaustive: false, serde)]
+#[derive(Eq, PartialEq, Debug)]
+enum Mode {
+    Off = 0,
+    On = 1,
+    Blinking = 2,
+}
+
+#[test]
+fn bitenum_as_name() {
+    assert_eq!(
+        "\"Blinking\"",
+        serde_json::to_string(&Mode::Blinking).unwrap()
+    );
+    assert_eq!(Mode::On, serde_json::from_str::<Mode>("\"On\"").unwrap());
+
+    // Raw values are accepted as well, as long as they are valid
+    assert_eq!(Mode::Off, serde_json::from_str::<Mode>("0").unwrap());
+    assert!(serde_json::from_str::<Mode>("3").is_err());
+    assert!(serde_json::from_str::<Mode>("4").is_err());
+    assert!(serde_json::from_str::<Mode>("\"Unknown\"").is_err());
+}
+
+#[test]
+fn bitenum_with_fallback() {
+    #[bitenum(u3, exhaustive: false, fallback: Unknown, serde)]
+    #[derive(Eq, PartialEq, Debug)]
+    enum Command {
+        Read = 0,
+        Write = 1,
+        Unknown(u3),
+    }
+
+    assert_eq!("\"Write\"", serde_json::to_string(&Command::Write).unwrap());
+    // Values without a variant are serialized as raw value
+    assert_eq!(
+        "6",
+        serde_json::to_string(&Command::Unknown(u3::new(6))).unwrap()
+    );
+
+    assert_eq!(
+        Command::Read,
+        serde_json::from_str::<Command>("\"Read\"").unwrap()
+    );
+    assert_eq!(
+        Command::Unknown(u3::new(6)),
+        serde_json::from_str::<Command>("6").unwrap()
+    );
+    assert!(serde_json::from_str::<Command>("8").is_err());
+    assert!(serde_json::from_str::<Command>("\"Unknown\"").is_err());
+}
+
+#[test]
+fn bitfield_as_raw_value() {
+    #[bitfield(u32, serde: raw)]
+    #[derive(Debug)]
+    struct Test {
+        #[bits(0..=7, rw)]
+        value: u8,
+    }
+
+    let t = Test::new_with_raw_value(0x1234_5678);
+    assert_eq!("305419896", serde_json::to_string(&t).unwrap());
+    assert_eq!(
+        0x1234_5678,
+        serde_json::from_str::<Test>("305419896")
+            .unwrap()
+            .raw_value()
+    );
+
+    #[bitfield([u8; 3], serde: raw)]
+    struct ArrayBased {
+        #[bits(4..=11, rw)]
+        value: u8,
+    }
+    let t = ArrayBased::new_with_raw_value([1, 2, 3]);
+    assert_eq!("[1,2,3]", serde_json::to_string(&t).unwrap());
+    assert_eq!(
+        [4, 5, 6],
+        serde_json::from_str::<ArrayBased>("[4,5,6]")
+            .unwrap()
+            .raw_value()
+    );
+    assert!(serde_json::from_str::<ArrayBased>("[4,5]").is_err());
+
+    #[bitfield(u5, serde: raw)]
+    struct Small {
+        #[bits(0..=4, rw)]
+        value: u5,
+    }
+    assert_eq!(
+        u5::new(31),
+        serde_json::from_str::<Small>("31").unwrap().raw_value()
+    );
+    assert!(serde_json::from_str::<Small>("32").is_err());
+
+    // Reserved bits are validated
+    #[bitfield(u8, serde: raw)]
+    struct WithReserved {
+        #[bits(4..=7, reserved: 0)]
+        _reserved: u4,
+
+        #[bits(0..=3, rw)]
+        value: u4,
+    }
+    assert!(serde_json::from_str::<WithReserved>("15").is_ok());
+    assert!(serde_json::from_str::<WithReserved>("16").is_err());
+}
+
+#[test]
+fn bitfield_as_fields() {
+    #[bitfield(u32, default: 0, serde: fields)]
+    struct Test {
+        #[bits(24..=31, r)]
+        status: u8,
+
+        #[bits(16..=23, w)]
+        command: u8,
+
+        #[bits(12..=13, rw)]
+        mode: Option<Mode>,
+
+        #[bits(8..=11, rw)]
+        nibble: u4,
+
+        #[bit(0, rw)]
+        flag: [bool; 4],
+    }
+
+    let t = Test::new_with_raw_value(0x1200_2A05);
+    let json = serde_json::to_string(&t).unwrap();
+    assert_eq!(
+        r#"{"status":18,"mode":"Blinking","nibble":10,"flag":[true,false,true,false]}"#,
+        json
+    );
+    assert_eq!(
+        0x1200_2A05,
+        serde_json::from_str::<Test>(&json).unwrap().raw_value()
+    );
+
+    // Unknown enum values are given as raw number
+    let t = Test::new_with_raw_value(0x0000_3000);
+    let json = serde_json::to_string(&t).unwrap();
+    assert_eq!(
+        r#"{"status":0,"mode":3,"nibble":0,"flag":[false,false,false,false]}"#,
+        json
+    );
+    assert_eq!(
+        0x0000_3000,
+        serde_json::from_str::<Test>(&json).unwrap().raw_value()
+    );
+
+    // Missing fields keep their default, unknown fields are ignored. Write-only fields can be
+    // deserialized, even though they aren't serialized
+    assert_eq!(
+        0x0034_1000,
+        serde_json::from_str::<Test>(r#"{"mode":1,"command":52,"unknown":5}"#)
+            .unwrap()
+            .raw_value()
+    );
+    assert!(serde_json::from_str::<Test>(r#"{"mode":4}"#).is_err());
+    assert!(serde_json::from_str::<Test>(r#"{"mode":"Unknown"}"#).is_err());
+
+    // Arbitrary ints and arrays of enums
+    #[bitfield([u8; 2], serde: fields)]
+    struct ArrayBased {
+        #[bits(10..=11, rw)]
+        small: u2,
+
+        #[bits(0..=1, rw)]
+        modes: [Option<Mode>; 4],
+    }
+    let json = r#"{"small":2,"modes":["On",3,"Off","Blinking"]}"#;
+    let t = serde_json::from_str::<ArrayBased>(json).unwrap();
+    assert_eq!([0x8D, 0x08], t.raw_value());
+    assert_eq!(json, serde_json::to_string(&t).unwrap());
+}
+
+#[test]
+fn bitenum_uses_name_attribute() {
+    #[bitenum(u2, exhaustive: true, serde)]
+    #[derive(Eq, PartialEq, Debug)]
+    enum Speed {
+        #[name = "slow"]
+        Slow = 0,
+        #[name = "fast"]
+        Fast = 1,
+        Turbo = 2,
+        Off = 3,
+    }
+
+    assert_eq!("\"fast\"", serde_json::to_string(&Speed::Fast).unwrap());
+    assert_eq!("\"Turbo\"", serde_json::to_string(&Speed::Turbo).unwrap());
+    assert_eq!(
+        Speed::Slow,
+        serde_json::from_str::<Speed>("\"slow\"").unwrap()
+    );
+    assert!(serde_json::from_str::<Speed>("\"Slow\"").is_err());
+}
+
+#[test]
+fn formats_that_are_not_human_readable() {
+    // Formats like postcard don't describe their data, so bitenums and bitfields in fields mode
+    // are given as raw value
+    #[bitenum(u3, exhaustive: false, fallback: Unknown, serde)]
+    #[derive(Eq, PartialEq, Debug)]
+    enum Command {
+        Read = 0,
+        Write = 1,
+        Unknown(u3),
+    }
+
+    let bytes = postcard::to_allocvec(&Mode::Blinking).unwrap();
+    assert_eq!(vec![2], bytes);
+    assert_eq!(
+        Mode::Blinking,
+        postcard::from_bytes::<Mode>(&bytes).unwrap()
+    );
+    assert!(postcard::from_bytes::<Mode>(&[3]).is_err());
+
+    let bytes = postcard::to_allocvec(&Command::Unknown(u3::new(6))).unwrap();
+    assert_eq!(
+        Command::Unknown(u3::new(6)),
+        postcard::from_bytes::<Command>(&bytes).unwrap()
+    );
+    assert_eq!(
+        Command::Write,
+        postcard::from_bytes::<Command>(&[1]).unwrap()
+    );
+
+    #[bitfield(u32, default: 0, serde: fields)]
+    struct Test {
+        #[bits(24..=31, r)]
+        status: u8,
+
+        #[bits(12..=13, rw)]
+        mode: Option<Mode>,
+
+        #[bit(0, rw)]
+        flag: [bool; 4],
+    }
+    let t = Test::new_with_raw_value(0x1200_3005);
+    let bytes = postcard::to_allocvec(&t).unwrap();
+    assert_eq!(
+        0x1200_3005,
+        postcard::from_bytes::<Test>(&bytes).unwrap().raw_value()
+    );
+}
+
+#[test]
+fn helpers_are_not_part_of_the_bitfield() {
+    #[bitfield(u16, default: 0, serde: fields)]
+    struct Test {
+        #[bits(0..=7, rw)]
+        value: u8,
+    }
+
+    // The serde implementation doesn't add functions or constants to the bitfield
+    impl Test {
+        const FIELDS: u8 = 0x34;
+
+        fn deserialize_field(self) -> u8 {
+            self.value()
+        }
+    }
+
+    let t = serde_json::from_str::<Test>(r#"{"value":18}"#).unwrap();
+    assert_eq!(0x12, t.deserialize_field());
+    assert_eq!(0x34, Test::FIELDS);
+}
@@ -0,0 +1,10 @@
+use bitbybit::bitenum;
+
+#[bitenum(u2, exhaustive: maybe)]
+enum Test {
+    A = 0,
+    B = 4,
+    C,
+}
+
+fn main() {}
@@ -0,0 +1,32 @@
+use bitbybit::bitenum;
+
+#[bitenum(u2, exhaustive: true, fallback: Unknown)]
+enum Exhaustive {
+    A = 0,
+    B = 1,
+    C = 2,
+    D = 3,
+    Unknown(arbitrary_int::u2),
+}
+
+#[bitenum(u2, exhaustive: false, fallback: Missing)]
+enum Missing {
+    A = 0,
+}
+
+#[bitenum(u2, exhaustive: false, fallback: Unknown)]
+enum NoValue {
+    A = 0,
+    Unknown = 1,
+}
+
+#[bitenum(u2, exhaustive: false, fallback: Unknown)]
+enum Unused {
+    A = 0,
+    B = 1,
+    C = 2,
+    D = 3,
+    Unknown(arbitrary_int::u2),
+}
+
+fn main() {}
@@ -0,0 +1,37 @@
+use bitbybit::bitenum;
+
+#[bitenum(u2, exhaustive: false, from_str: yes)]
+enum UnknownOption {
+    A = 0,
+}
+
+#[bitenum(u2, exhaustive: false)]
+enum InvalidName {
+    #[name(A)]
+    A = 0,
+    #[name = 1]
+    B = 1,
+}
+
+#[bitenum(u2, exhaustive: false)]
+enum DuplicateName {
+    #[name = "B"]
+    A = 0,
+    B = 1,
+}
+
+#[bitenum(u2, exhaustive: false, from_str: case_insensitive)]
+enum DuplicateNameIgnoringCase {
+    #[name = "b"]
+    A = 0,
+    B = 1,
+}
+
+#[bitenum(u2, exhaustive: false)]
+enum GeneratedName {
+    ALL = 0,
+    COUNT = 1,
+    Iter = 2,
+}
+
+fn main() {}
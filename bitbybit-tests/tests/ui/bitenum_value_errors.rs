@@ -0,0 +1,33 @@
+use bitbybit::bitenum;
+
+const BASE: u8 = 6;
+
+#[bitenum(u2, exhaustive: false)]
+enum KnownDuplicate {
+    A = 1,
+    B = 0,
+    C,
+}
+
+#[bitenum(u2, exhaustive: false)]
+enum ImplicitOutOfBounds {
+    A = 3,
+    B,
+}
+
+#[bitenum(u3, exhaustive: false)]
+#[repr(u8)]
+enum ComputedOutOfBounds {
+    A = BASE,
+    B,
+    C,
+}
+
+#[bitenum(u3, exhaustive: false, fallback: Unknown)]
+enum ComputedDuplicate {
+    A = BASE,
+    B = 6,
+    Unknown(arbitrary_int::u3),
+}
+
+fn main() {}
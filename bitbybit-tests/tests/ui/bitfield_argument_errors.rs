@@ -0,0 +1,15 @@
+use bitbybit::bitfield;
+
+#[bitfield(u32, default: 0, debug, default: 1, unknown, bit_order: msb1, flags: true)]
+struct Test {
+    #[bits(0..=7, rw)]
+    value: u8,
+}
+
+#[bitfield(i32)]
+struct Signed {
+    #[bits(0..=7, rw)]
+    value: u8,
+}
+
+fn main() {}
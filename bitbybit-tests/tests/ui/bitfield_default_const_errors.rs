@@ -0,0 +1,19 @@
+use arbitrary_int::{u20, u4};
+use bitbybit::bitfield;
+
+const LARGE: u8 = 20;
+
+// Defaults that aren't literals are checked by evaluating new() at compile time
+#[bitfield(u32)]
+struct ConstDefaultDoesNotFit {
+    #[bits(0..=3, rw, default: u4::new(LARGE))]
+    a: u4,
+}
+
+#[bitfield(u20, default: LARGE as u32 * 0x10000)]
+struct BaseDefaultDoesNotFit {
+    #[bits(0..=19, rw)]
+    a: u20,
+}
+
+fn main() {}
@@ -0,0 +1,34 @@
+use arbitrary_int::{u2, u20, u4};
+use bitbybit::bitfield;
+
+#[bitfield(u32, default: 0x1234)]
+struct Conflicting {
+    #[bits(0..=7, rw, default: 5)]
+    a: u8,
+
+    #[bits(8..=15, rw, default: 6)]
+    b: u8,
+}
+
+#[bitfield(u32)]
+struct Reserved {
+    #[bits(0..=7, reserved: 0, default: 5)]
+    _reserved: u8,
+}
+
+#[bitfield(u32)]
+struct FieldDefaultDoesNotFit {
+    #[bits(0..=3, rw, default: 20)]
+    a: u4,
+
+    #[bits(4..=5, rw, default: [1, 4])]
+    b: [u2; 2],
+}
+
+#[bitfield(u20, default: 0x1FFFFF)]
+struct DefaultDoesNotFit {
+    #[bits(0..=19, rw)]
+    a: u20,
+}
+
+fn main() {}
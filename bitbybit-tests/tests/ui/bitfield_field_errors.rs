@@ -0,0 +1,22 @@
+use bitbybit::bitfield;
+
+// All fields with errors are reported at once
+#[bitfield(u32)]
+struct Test {
+    #[bits(0..=7, rx)]
+    unknown_specifier: u8,
+
+    #[bits(28..=35, rw)]
+    out_of_range: u8,
+
+    #[bits(8..=15, rw)]
+    wrong_type: u16,
+
+    #[bit(16..=17, rw)]
+    range_in_bit: bool,
+
+    #[bits(24..=31, rw, stride: 8)]
+    stride_without_array: u8,
+}
+
+fn main() {}
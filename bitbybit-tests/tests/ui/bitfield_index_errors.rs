@@ -0,0 +1,15 @@
+use bitbybit::bitfield;
+
+#[bitfield(u32)]
+struct Test {
+    #[bits(0..=7, rw, index: u3)]
+    too_small: [u8; 2],
+
+    #[bit(8, rw, index: u2)]
+    not_a_power_of_two: [bool; 3],
+
+    #[bit(16, rw, index: i4)]
+    signed: [bool; 16],
+}
+
+fn main() {}
@@ -0,0 +1,21 @@
+use bitbybit::bitfield;
+
+#[bitfield(u32)]
+struct Test {
+    #[bits(0..=7, rw)]
+    a: u8,
+
+    #[bits(4..=11, rw)]
+    b: u8,
+
+    #[bits(16..=23, rw, overlaps: missing)]
+    c: u8,
+}
+
+#[bitfield(u64)]
+struct ArrayOutOfRange {
+    #[bits(0..=3, rw)]
+    nibbles: [arbitrary_int::u4; 20],
+}
+
+fn main() {}
@@ -0,0 +1,20 @@
+#![deny(unused_must_use)]
+
+use bitbybit::bitfield;
+
+#[bitfield(u8)]
+struct Status {
+    #[bit(0, w1s)]
+    start: bool,
+
+    #[bit(1, w1c)]
+    done: bool,
+}
+
+fn main() {
+    let status = Status::new_with_raw_value(0);
+    // These return a new value and don't change status
+    status.trigger_start();
+    status.clear_done();
+    status.without_side_effects();
+}
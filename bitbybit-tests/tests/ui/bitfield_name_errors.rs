@@ -0,0 +1,39 @@
+use bitbybit::bitfield;
+
+#[bitfield(u32)]
+struct Accessors {
+    #[bit(0, rw)]
+    flag: [bool; 8],
+
+    #[bits(8..=15, rw)]
+    flag_array: u8,
+}
+
+#[bitfield(u32)]
+struct Functions {
+    #[bits(0..=7, rw)]
+    level: [u8; 2],
+
+    #[bits(16..=23, r)]
+    level_mask: u8,
+}
+
+#[bitfield(u32, flags)]
+struct Flags {
+    #[bits(0..=7, rw)]
+    ready: u8,
+
+    #[bit(8, rw)]
+    ready_width: bool,
+}
+
+#[bitfield(u32)]
+struct StructFunctions {
+    #[bits(0..=7, rw)]
+    raw_value: u8,
+
+    #[bits(8..=15, rw)]
+    __bitbybit_insert_raw_bits: u8,
+}
+
+fn main() {}
@@ -0,0 +1,15 @@
+use bitbybit::bitfield;
+
+#[bitfield(u8)]
+struct Inner {
+    #[bits(0..=7, rw)]
+    value: u8,
+}
+
+#[bitfield(u32)]
+struct Outer {
+    #[bits(0..=5, rw)]
+    inner: Inner,
+}
+
+fn main() {}
@@ -0,0 +1,123 @@
+use std::fmt::Display;
+
+use proc_macro2::{Span, TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::parse::{Parse, ParseStream};
+use syn::{Ident, Token};
+
+/// Collects errors, so that all of them can be reported at once instead of stopping at the first one
+#[derive(Default)]
+pub struct Errors(Option<syn::Error>);
+
+impl Errors {
+    pub fn push(&mut self, error: syn::Error) {
+        match &mut self.0 {
+            Some(errors) => errors.combine(error),
+            None => self.0 = Some(error),
+        }
+    }
+
+    pub fn add<T: Display>(&mut self, span: Span, message: T) {
+        self.push(syn::Error::new(span, message));
+    }
+
+    pub fn add_spanned<T: ToTokens, U: Display>(&mut self, tokens: T, message: U) {
+        self.push(syn::Error::new_spanned(tokens, message));
+    }
+
+    /// Returns all errors that were collected so far
+    pub fn check(&mut self) -> syn::Result<()> {
+        match self.0.take() {
+            Some(errors) => Err(errors),
+            None => Ok(()),
+        }
+    }
+}
+
+/// An argument like 'debug' or 'default: 0x1234'. Values are kept as tokens, as their meaning depends
+/// on the argument
+pub struct Argument {
+    pub name: Ident,
+    pub value: Option<TokenStream>,
+}
+
+impl Parse for Argument {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name: Ident = input.parse()?;
+        if !input.peek(Token![:]) {
+            return Ok(Argument { name, value: None });
+        }
+        let colon: Token![:] = input.parse()?;
+        // The value extends up to the next comma. Arrays like [0; 16] are a single token tree
+        let mut value = TokenStream::new();
+        while !input.is_empty() && !input.peek(Token![,]) {
+            value.extend([input.parse::<TokenTree>()?]);
+        }
+        if value.is_empty() {
+            return Err(syn::Error::new_spanned(
+                colon,
+                format!("Expected value after '{}:'", name),
+            ));
+        }
+        Ok(Argument {
+            name,
+            value: Some(value),
+        })
+    }
+}
+
+impl Argument {
+    /// Returns the value of a key:value argument. Reports an error if the value is missing
+    pub fn expect_value(&self, errors: &mut Errors) -> Option<&TokenStream> {
+        if self.value.is_none() {
+            errors.add(
+                self.name.span(),
+                format!("Expected a value, e.g. '{}: value'", self.name),
+            );
+        }
+        self.value.as_ref()
+    }
+
+    /// Reports an error if a flag like 'debug' was given a value
+    pub fn expect_flag(&self, errors: &mut Errors) {
+        if let Some(value) = &self.value {
+            errors.add_spanned(value, format!("{} doesn't take a value", self.name));
+        }
+    }
+}
+
+/// Parses the comma separated arguments that follow the first argument, e.g. ', default: 0, debug'
+pub fn parse_arguments(input: ParseStream) -> syn::Result<Vec<Argument>> {
+    let mut arguments = Vec::new();
+    while !input.is_empty() {
+        input.parse::<Token![,]>()?;
+        if input.is_empty() {
+            break;
+        }
+        arguments.push(input.parse()?);
+    }
+    Ok(arguments)
+}
+
+/// Reports an error for every argument that is given more than once. Returns the arguments that
+/// were given for the first time
+pub fn unique_arguments<'a>(
+    arguments: impl IntoIterator<Item = &'a Argument>,
+    errors: &mut Errors,
+) -> impl Iterator<Item = &'a Argument> {
+    let mut seen: Vec<String> = Vec::new();
+    let mut unique = Vec::new();
+    for argument in arguments {
+        let name = argument.name.to_string();
+        if seen.contains(&name) {
+            errors.add(
+                argument.name.span(),
+                format!("{} must only be specified at most once", name),
+            );
+        } else {
+            seen.push(name);
+            unique.push(argument);
+        }
+    }
+    unique.into_iter()
+}
@@ -1,171 +1,435 @@
 use proc_macro::TokenStream;
 use std::str::FromStr;
 
-use proc_macro2::TokenTree;
-use quote::{quote, ToTokens};
+use proc_macro2::Literal;
+use quote::{format_ident, quote, quote_spanned, ToTokens};
 use syn::__private::TokenStream2;
-use syn::{Data, DeriveInput, Expr, Ident};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{Data, DeriveInput, Expr, Fields, Ident, Lit, Meta, MetaNameValue, Token, Variant};
 
-pub fn bitenum(args: TokenStream, input: TokenStream) -> TokenStream {
-    let args: Vec<_> = proc_macro2::TokenStream::from(args).into_iter().collect();
+use crate::arguments::{parse_arguments, unique_arguments, Argument, Errors};
 
-    let mut bits: Option<usize> = None;
-    let mut exhaustive_value: Option<TokenStream2> = None;
+/// The arguments of #[bitenum(...)]: the base data type, followed by optional arguments
+struct BitenumArguments {
+    base_data_type: Ident,
+    arguments: Vec<Argument>,
+}
 
-    enum ArgumentType {
-        Exhaustive,
+impl Parse for BitenumArguments {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.is_empty() {
+            return Err(input
+                .error("datatype argument needed, for example #[bitenum(u4, exhaustive: true)]"));
+        }
+        Ok(BitenumArguments {
+            base_data_type: input.parse()?,
+            arguments: parse_arguments(input)?,
+        })
     }
-    let mut next_expected: Option<ArgumentType> = None;
+}
 
-    fn handle_next_expected(
-        next_expected: &Option<ArgumentType>,
-        default_value: &mut Option<TokenStream2>,
-        token_stream: TokenStream2,
-    ) {
-        match next_expected {
-            None => panic!("bitenum!: Seen {}, but didn't expect anything. Example of valid syntax: #[bitenum(u3, exhaustive: false)]", token_stream.to_string()),
-            Some(ArgumentType::Exhaustive) => {
-                *default_value = Some(token_stream);
-            }
+/// The value of a variant
+enum VariantValue {
+    /// A value that is known to the macro, e.g. a literal
+    Known(u128),
+    /// An expression that evaluates to the value as u128 in a const context
+    Computed(TokenStream2),
+}
+
+impl VariantValue {
+    /// Returns tokens that evaluate to the value as u128
+    fn to_u128_tokens(&self) -> TokenStream2 {
+        match self {
+            VariantValue::Known(value) => Literal::u128_suffixed(*value).into_token_stream(),
+            VariantValue::Computed(expression) => expression.clone(),
         }
     }
-    for i in 0..args.len() {
-        match &args[i] {
-            TokenTree::Punct(p) => match p.to_string().as_str() {
-                "," => next_expected = None,
-                ":" => {}
-                _ => panic!(
-                    "bitenum!: Expected ',' or ':' in argument list. Seen '{}'",
-                    p.to_string()
-                ),
-            },
-            TokenTree::Ident(sym) => {
-                if next_expected.is_some() {
-                    // We might end up here if we refer to a constant, like 'exhaustive: SOME_CONSTANT'
-                    handle_next_expected(
-                        &next_expected,
-                        &mut exhaustive_value,
-                        sym.to_token_stream(),
-                    );
-                } else {
-                    match sym.to_string().as_str() {
-                        "exhaustive" => {
-                            if exhaustive_value.is_some() {
-                                panic!("bitenum!: exhaustive must only be specified at most once");
-                            }
-                            next_expected = Some(ArgumentType::Exhaustive)
-                        }
-                        s => {
-                            // See if this is a base datatype like u3
-                            let size = if s.starts_with("u") {
-                                let num = usize::from_str(s.split_at(1).1);
-                                if let Ok(num) = num {
-                                    if num <= 64 {
-                                        Some(num)
-                                    } else {
-                                        None
-                                    }
-                                } else {
-                                    None
-                                }
-                            } else {
-                                None
-                            };
 
-                            match size {
-                                Some(size) => bits = Some(size),
-                                None => panic!("bitenum!: Unexpected argument {}. Supported: u1, u2, u3, .., u64 and 'exhaustive'", sym.to_string()),
-                            }
+    /// Returns a pattern that matches the raw value (which is bound to 'raw' in case of an
+    /// expression)
+    fn to_pattern(&self) -> TokenStream2 {
+        match self {
+            VariantValue::Known(value) => Literal::u128_unsuffixed(*value).into_token_stream(),
+            VariantValue::Computed(expression) => quote! { raw if raw as u128 == #expression },
+        }
+    }
+}
+
+/// Parses hexadecimal, octal, binary and decimal integer literals. Returns None for anything else
+fn parse_integer(expression: &Expr) -> Option<u128> {
+    let string_value = expression.to_token_stream().to_string().replace('_', "");
+    if let Some(hex) = string_value.strip_prefix("0x") {
+        u128::from_str_radix(hex, 16)
+    } else if let Some(binary) = string_value.strip_prefix("0b") {
+        u128::from_str_radix(binary, 2)
+    } else if let Some(octal) = string_value.strip_prefix("0o") {
+        u128::from_str_radix(octal, 8)
+    } else {
+        string_value.parse::<u128>()
+    }
+    .ok()
+}
+
+pub fn bitenum(args: TokenStream, input: TokenStream) -> TokenStream {
+    match generate(args.into(), input.into()) {
+        Ok(expanded) => TokenStream::from(expanded),
+        Err(errors) => TokenStream::from(errors.to_compile_error()),
+    }
+}
+
+fn generate(args: TokenStream2, input: TokenStream2) -> syn::Result<TokenStream2> {
+    let BitenumArguments {
+        base_data_type,
+        arguments,
+    } = syn::parse2(args)?;
+    // Errors are collected, so that all of them can be reported at once
+    let mut errors = Errors::default();
+
+    let mut exhaustive_value: Option<TokenStream2> = None;
+    let mut fallback_value: Option<TokenStream2> = None;
+    let mut from_str_value: Option<Option<TokenStream2>> = None;
+    let mut serde = false;
+    let mut defmt = false;
+    let mut display = false;
+    let mut name_function = false;
+
+    for argument in unique_arguments(&arguments, &mut errors) {
+        match argument.name.to_string().as_str() {
+            "exhaustive" => exhaustive_value = argument.expect_value(&mut errors).cloned(),
+            "fallback" => fallback_value = argument.expect_value(&mut errors).cloned(),
+            // from_str optionally takes 'case_insensitive'
+            "from_str" => from_str_value = Some(argument.value.clone()),
+            name => {
+                argument.expect_flag(&mut errors);
+                match name {
+                    "serde" => {
+                        if !cfg!(feature = "serde") {
+                            errors.add(
+                                argument.name.span(),
+                                "serde requires the 'serde' feature of bitbybit",
+                            );
                         }
+                        serde = true
                     }
-                }
-            }
-            TokenTree::Literal(literal) => {
-                // We end up here if we see a literal, like 'exhaustive: true'
-                let default_value = match next_expected {
-                    None => {
-                        panic!()
+                    "defmt" => {
+                        if !cfg!(feature = "defmt") {
+                            errors.add(
+                                argument.name.span(),
+                                "defmt requires the 'defmt' feature of bitbybit",
+                            );
+                        }
+                        defmt = true
                     }
-                    Some(ArgumentType::Exhaustive) => &mut exhaustive_value,
-                };
-                handle_next_expected(&next_expected, default_value, literal.to_token_stream());
-            }
-            _ => {
-                panic!("bitenum!: Unexpected token. Example of valid syntax: #[bitenum(u32, exhaustive: true)]")
+                    "display" => display = true,
+                    "name" => name_function = true,
+                    _ => errors.add(
+                        argument.name.span(),
+                        format!(
+                            "Unexpected argument {}. Supported: 'exhaustive', 'fallback', 'serde', 'defmt', 'display', 'from_str' and 'name'",
+                            name
+                        ),
+                    ),
+                }
             }
         }
     }
 
+    // The base data type is an unsigned int like u3
+    let bits = base_data_type
+        .to_string()
+        .strip_prefix('u')
+        .and_then(|size| usize::from_str(size).ok())
+        .filter(|size| (1..=64).contains(size))
+        .ok_or_else(|| {
+            syn::Error::new_spanned(
+                &base_data_type,
+                format!(
+                    "Unexpected base data type {}. Supported: u1, u2, u3, .., u64",
+                    base_data_type
+                ),
+            )
+        })?;
+
+    let base_data_type_name = base_data_type.to_string();
     let (bit_count, base_data_type, bounded_data_type, result_constructor, bounded_getter) =
         match bits {
-            Some(bit_count) => {
-                match bit_count {
-                    b if b < 8 => (b, quote! { u8 }, quote! { arbitrary_int::UInt::<u8, #b> }, quote! { arbitrary_int::UInt::<u8, #b>::new }, quote! { .value() }),
-                    b if b == 8 => (b, quote! { u8 }, quote! { u8 }, quote! { }, quote! { }),
-                    b if b < 16 => (b, quote! { u16 }, quote! { arbitrary_int::UInt::<u16, #b> }, quote! { arbitrary_int::UInt::<u16, #b>::new }, quote! { .value() }),
-                    b if b == 16 => (b, quote! { u16 }, quote! { u16 }, quote! { }, quote! { }),
-                    b if b < 32 => (b, quote! { u32 }, quote! { arbitrary_int::UInt::<u32, #b> }, quote! { arbitrary_int::UInt::<u32, #b>::new }, quote! { .value() }),
-                    b if b == 32 => (b, quote! { u32 }, quote! { u32 }, quote! { }, quote! { }),
-                    b if b < 64 => (b, quote! { u64 }, quote! { arbitrary_int::UInt::<u64, #b> }, quote! { arbitrary_int::UInt::<u64, #b>::new }, quote! { .value() }),
-                    b if b == 64 => (b, quote! { u64 }, quote! { u64 }, quote! { }, quote! { }),
-                    _ => panic!("bitenum!: Unhandled bits. Supported up to u64"),
-                }
-            }
-            None => panic!("bitenum!: datatype argument needed, for example #[bitenum(u4, exhaustive: true)"),
+            b if b < 8 => (
+                b,
+                quote! { u8 },
+                quote! { arbitrary_int::UInt::<u8, #b> },
+                quote! { arbitrary_int::UInt::<u8, #b>::new },
+                quote! { .value() },
+            ),
+            b if b == 8 => (b, quote! { u8 }, quote! { u8 }, quote! {}, quote! {}),
+            b if b < 16 => (
+                b,
+                quote! { u16 },
+                quote! { arbitrary_int::UInt::<u16, #b> },
+                quote! { arbitrary_int::UInt::<u16, #b>::new },
+                quote! { .value() },
+            ),
+            b if b == 16 => (b, quote! { u16 }, quote! { u16 }, quote! {}, quote! {}),
+            b if b < 32 => (
+                b,
+                quote! { u32 },
+                quote! { arbitrary_int::UInt::<u32, #b> },
+                quote! { arbitrary_int::UInt::<u32, #b>::new },
+                quote! { .value() },
+            ),
+            b if b == 32 => (b, quote! { u32 }, quote! { u32 }, quote! {}, quote! {}),
+            b if b < 64 => (
+                b,
+                quote! { u64 },
+                quote! { arbitrary_int::UInt::<u64, #b> },
+                quote! { arbitrary_int::UInt::<u64, #b>::new },
+                quote! { .value() },
+            ),
+            _ => (64, quote! { u64 }, quote! { u64 }, quote! {}, quote! {}),
         };
 
-    let is_exhaustive = exhaustive_value
-        .map(|x| x.to_string() == "true")
-        .unwrap_or(false);
+    let is_exhaustive = match exhaustive_value {
+        None => false,
+        Some(value) => match value.to_string().as_str() {
+            "true" => true,
+            "false" => false,
+            s => {
+                errors.add_spanned(
+                    &value,
+                    format!("Unhandled exhaustive '{}'. Expected 'true' or 'false'", s),
+                );
+                false
+            }
+        },
+    };
+
+    let from_str = match from_str_value {
+        None => None,
+        Some(None) => Some(false),
+        Some(Some(value)) => match value.to_string().as_str() {
+            "case_insensitive" => Some(true),
+            s => {
+                errors.add_spanned(
+                    &value,
+                    format!("Unhandled from_str '{}'. Expected 'case_insensitive'", s),
+                );
+                None
+            }
+        },
+    };
+    // name() is used by Display and FromStr
+    let name_function = name_function || display || from_str.is_some();
+
+    // The fallback variant holds all raw values that don't have a variant of their own
+    let fallback = fallback_value.and_then(|value| {
+        syn::parse2::<Ident>(value.clone())
+            .map_err(|_| {
+                errors.add_spanned(
+                    &value,
+                    "Expected the name of a variant, e.g. 'fallback: Unknown'",
+                )
+            })
+            .ok()
+    });
+    if is_exhaustive {
+        if let Some(fallback) = &fallback {
+            errors.add_spanned(
+                fallback,
+                "An exhaustive enum can't have a fallback variant. Remove 'exhaustive: true'",
+            );
+        }
+    }
 
-    let input = syn::parse_macro_input!(input as DeriveInput);
+    let input = syn::parse2::<DeriveInput>(input)?;
     let enum_name = input.ident;
     let enum_vis = input.vis;
     let enum_attrs = input.attrs;
 
     let variants = match input.data {
         Data::Enum(enum_data) => enum_data.variants,
-        _ => panic!("bitenum!: Must be used on enum"),
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &enum_name,
+                "bitenum must be used on an enum",
+            ))
+        }
     };
-    let emitted_variants: Vec<(&Expr, u128, &Ident)> = variants.iter().map(|variant| {
+    // Variants share a namespace with the functions and constants that are generated for the enum
+    let generated_names = [
+        "COUNT",
+        "ALL",
+        "variant",
+        "iter",
+        "new_with_raw_value",
+        "raw_value",
+    ];
+    for variant in &variants {
+        let variant_name = variant.ident.to_string();
+        if generated_names.contains(&variant_name.as_str())
+            || (name_function && variant_name == "name")
+        {
+            errors.add_spanned(
+                &variant.ident,
+                format!(
+                    "Variant {} has the same name as a generated function or constant. Rename the variant",
+                    variant.ident
+                ),
+            );
+        }
+    }
+
+    // The names used by name(), Display and FromStr. #[name = "..."] overrides the variant name
+    let mut variant_name_strings: Vec<String> = Vec::with_capacity(variants.len());
+    for variant in &variants {
+        let mut name = variant.ident.to_string();
+        for attribute in variant
+            .attrs
+            .iter()
+            .filter(|attr| attr.path.is_ident("name"))
+        {
+            match attribute.parse_meta() {
+                Ok(Meta::NameValue(MetaNameValue {
+                    lit: Lit::Str(lit), ..
+                })) => name = lit.value(),
+                _ => errors.add_spanned(attribute, "Expected a name like #[name = \"Name\"]"),
+            }
+        }
+        let is_duplicate = variant_name_strings.iter().any(|other| {
+            if from_str == Some(true) {
+                other.eq_ignore_ascii_case(&name)
+            } else {
+                *other == name
+            }
+        });
+        if is_duplicate {
+            errors.add_spanned(
+                &variant.ident,
+                format!("Name '{}' is used by multiple variants", name),
+            );
+        }
+        variant_name_strings.push(name);
+    }
+
+    // The value of a variant is known to the macro if it is a literal (or follows one). Otherwise,
+    // it is a const expression that is checked by generated const assertions
+    let max_value = (1u128 << bit_count) - 1;
+    let mut emitted_variants: Vec<(VariantValue, &Ident)> = Vec::with_capacity(variants.len());
+    let mut const_assertions: Vec<TokenStream2> = Vec::new();
+    for variant in variants
+        .iter()
+        .filter(|variant| Some(&variant.ident) != fallback.as_ref())
+    {
         let variant_name = &variant.ident;
-        let discriminant = (&variant.discriminant).as_ref().expect(format!("bitenum!: Variant '{}' needs to have a value", variant_name).as_str());
         // Discriminant.0 is the equals sign. 1 is the value
-        let value = &discriminant.1;
-        let string_value = value.to_token_stream().to_string().replace("_", "");
-
-        // Determine the integer value itself. While we don't need it further down (for now),
-        // this ensures that only constants are being used; due to the way how new_with_raw_value()
-        // is written, some expressions would cause compilation issues (e.g. those that refer to other
-        // enum values).
-        let int_value = if string_value.starts_with("0x") {
-            u128::from_str_radix(&string_value[2..], 16)
-        } else if string_value.starts_with("0b") {
-            u128::from_str_radix(&string_value[2..], 2)
-        } else if string_value.starts_with("0o") {
-            u128::from_str_radix(&string_value[2..], 8)
-        } else {
-            u128::from_str_radix(&string_value, 10)
-        }.expect(format!("bitenum!: Error parsing '{}' as integer. Supported: hexadecimal, octal, binary and decimal unsigned integers, but not expressions", string_value).as_str());
-
-        if int_value >= (1u128 << bit_count) {
-            panic!("bitenum!: Value {} exceeds the given number of bits", variant_name);
+        let value = match &variant.discriminant {
+            // Like in Rust, a variant without a value follows the previous one
+            None => match emitted_variants.last() {
+                None => VariantValue::Known(0),
+                Some((VariantValue::Known(previous), _)) => VariantValue::Known(previous + 1),
+                Some((VariantValue::Computed(previous), _)) => {
+                    VariantValue::Computed(quote! { (#previous + 1) })
+                }
+            },
+            Some((_, expression)) => match parse_integer(expression) {
+                Some(int_value) => VariantValue::Known(int_value),
+                None => VariantValue::Computed(quote! { ((#expression) as u128) }),
+            },
+        };
+        let value_tokens = match &variant.discriminant {
+            Some((_, expression)) => expression.to_token_stream(),
+            None => variant_name.to_token_stream(),
+        };
+        let span = value_tokens.span();
+        match &value {
+            VariantValue::Known(int_value) => {
+                if *int_value > max_value {
+                    errors.add_spanned(
+                        value_tokens,
+                        format!("Value {} exceeds the given number of bits", variant_name),
+                    );
+                } else if let Some((_, other)) = emitted_variants.iter().find(
+                    |(other_value, _)| matches!(other_value, VariantValue::Known(other) if other == int_value),
+                ) {
+                    errors.add_spanned(
+                        value_tokens,
+                        format!("Variants {} and {} have the same value", other, variant_name),
+                    );
+                }
+            }
+            VariantValue::Computed(expression) => {
+                let message = format!("Value {} exceeds the given number of bits", variant_name);
+                const_assertions.push(quote_spanned! {span=>
+                    const _: () = assert!(#expression <= #max_value, #message);
+                });
+            }
+        }
+        // Without a fallback variant, the enum keeps its values and the compiler ensures that they
+        // are unique
+        if fallback.is_some() {
+            for (other_value, other) in &emitted_variants {
+                if let (VariantValue::Known(_), VariantValue::Known(_)) = (&value, other_value) {
+                    continue;
+                }
+                let message = format!(
+                    "Variants {} and {} have the same value",
+                    other, variant_name
+                );
+                let (expression, other_expression) =
+                    (value.to_u128_tokens(), other_value.to_u128_tokens());
+                const_assertions.push(quote_spanned! {span=>
+                    const _: () = assert!(#expression != #other_expression, #message);
+                });
+            }
         }
+        emitted_variants.push((value, variant_name));
+    }
 
-        (value, int_value, variant_name)
-    }).collect();
+    if let Some(fallback) = &fallback {
+        match variants.iter().find(|variant| variant.ident == *fallback) {
+            None => errors.add_spanned(
+                fallback,
+                format!("Fallback variant '{}' not found", fallback),
+            ),
+            Some(variant) => {
+                if variant.discriminant.is_some()
+                    || !matches!(&variant.fields, Fields::Unnamed(fields) if fields.unnamed.len() == 1)
+                {
+                    errors.add_spanned(
+                        variant,
+                        format!(
+                            "Fallback variant '{}' must hold the raw value and can't have a value itself, e.g. '{}({})'",
+                            fallback, fallback, base_data_type_name
+                        ),
+                    );
+                }
+            }
+        }
+    }
+    errors.check()?;
 
-    // We tested the numeric values for out-of-bounds above. As enum values are unique integers,
-    // we can now reason about the number of variants: If variants == 2^bits then we have to be exhaustive
+    // We tested the numeric values for out-of-bounds above (or generated const assertions that do
+    // so). As enum values are unique integers, we can now reason about the number of variants: If variants == 2^bits then we have to be exhaustive
     // (and if not, we can't be).
     let possible_maximum_variants = 1u128 << bit_count;
     if is_exhaustive {
         if emitted_variants.len() != possible_maximum_variants as usize {
-            panic!("bitenum!: Enum is marked as exhaustive, but it is missing variants")
+            return Err(syn::Error::new_spanned(
+                &enum_name,
+                "Enum is marked as exhaustive, but it is missing variants",
+            ));
         }
-    } else {
-        if emitted_variants.len() == possible_maximum_variants as usize {
-            panic!("bitenum!: Enum is exhaustive, but not marked accordingly. Add 'exhaustive: true'")
+    } else if emitted_variants.len() == possible_maximum_variants as usize {
+        if let Some(fallback) = &fallback {
+            return Err(syn::Error::new_spanned(
+                fallback,
+                "Every value has a variant, so the fallback variant is never used",
+            ));
+        } else {
+            return Err(syn::Error::new_spanned(
+                &enum_name,
+                "Enum is exhaustive, but not marked accordingly. Add 'exhaustive: true'",
+            ));
         }
     }
 
@@ -173,25 +437,48 @@ pub fn bitenum(args: TokenStream, input: TokenStream) -> TokenStream {
     // - match cases against every single integer
     // - exclude unhandled integers, followed by transmute (unsafe)
     // For now, we'll go with the first option. If we find that the compiler generates bad code,
-    // we can switch to the second option (as we required all values to be literals, so we can
+    // we can switch to the second option (at least for enums whose values are all known, so we can
     // analyse used vs unused ranges)
 
     let case_values: Vec<TokenStream2> = emitted_variants
         .iter()
-        .map(|(expression, _int_value, name)| {
-            if is_exhaustive {
+        .map(|(value, name)| {
+            let pattern = value.to_pattern();
+            if is_exhaustive || fallback.is_some() {
                 quote! {
-                    #expression => Self::#name,
+                    #pattern => Self::#name,
                 }
             } else {
                 quote! {
-                    #expression => Ok(Self::#name),
+                    #pattern => Ok(Self::#name),
                 }
             }
         })
         .collect();
 
-    let constructor_function = if is_exhaustive {
+    let constructor_function = if let Some(fallback) = &fallback {
+        let doc = format!(
+            "Values that don't have a variant are kept in {}, so this function will always return a valid result",
+            fallback
+        );
+        let fallback_doc = format!(
+            "Use this function rather than {}(..) to create values: {}(..) with the value of another variant isn't equal to that variant",
+            fallback, fallback
+        );
+        quote!(
+            /// Creates a new instance of this bitfield with the given raw value.
+            ///
+            #[doc = #doc]
+            ///
+            #[doc = #fallback_doc]
+            pub const fn new_with_raw_value(value: #bounded_data_type) -> Self {
+                match value #bounded_getter {
+                    #( #case_values )*
+                    _ => Self::#fallback(value)
+                }
+            }
+        )
+    } else if is_exhaustive {
         let panic_string = format!("{}: Unhandled value", enum_name);
         quote!(
             /// Creates a new instance of this bitfield with the given raw value.
@@ -217,6 +504,276 @@ pub fn bitenum(args: TokenStream, input: TokenStream) -> TokenStream {
         )
     };
 
+    // With serde, variants are serialized by name (as given by #[name = "..."]) in human readable
+    // formats. Deserialization accepts names as well as raw values. Formats that aren't human readable
+    // (like postcard or bincode) aren't self-describing, so they always use the raw value
+    let serde_implementations = if serde {
+        let enum_name_string = enum_name.to_string();
+        let variant_indices = 0..emitted_variants.len() as u32;
+        let variant_names: Vec<&Ident> = emitted_variants.iter().map(|(_, name)| *name).collect();
+        let variant_values: Vec<TokenStream2> = emitted_variants
+            .iter()
+            .map(|(value, _)| value.to_u128_tokens())
+            .collect();
+        let serde_name_strings: Vec<&String> = variant_names
+            .iter()
+            .map(|name| {
+                let index = variants
+                    .iter()
+                    .position(|variant| variant.ident == **name)
+                    .unwrap();
+                &variant_name_strings[index]
+            })
+            .collect();
+        // The fallback variant is serialized as its raw value
+        let serialize_fallback = fallback.as_ref().map(|fallback| {
+            quote! { Self::#fallback(value) => serializer.serialize_u64((*value) #bounded_getter as u64), }
+        });
+        let raw_value_to_result = if is_exhaustive || fallback.is_some() {
+            quote! { Ok(#enum_name::new_with_raw_value(#result_constructor(value as #base_data_type))) }
+        } else {
+            quote! {
+                #enum_name::new_with_raw_value(#result_constructor(value as #base_data_type))
+                    .map_err(|_| E::invalid_value(serde::de::Unexpected::Unsigned(value), &self))
+            }
+        };
+        let max_value = ((1u128 << bit_count) - 1) as u64;
+        quote! {
+            const _: () = {
+                impl serde::Serialize for #enum_name {
+                    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                        if !serializer.is_human_readable() {
+                            return match self {
+                                #( Self::#variant_names => serializer.serialize_u64((#variant_values) as u64), )*
+                                #serialize_fallback
+                            };
+                        }
+                        match self {
+                            #( Self::#variant_names => serializer.serialize_unit_variant(#enum_name_string, #variant_indices, #serde_name_strings), )*
+                            #serialize_fallback
+                        }
+                    }
+                }
+
+                impl<'de> serde::Deserialize<'de> for #enum_name {
+                    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                        const VARIANTS: &[&str] = &[#( #serde_name_strings ),*];
+                        struct EnumVisitor;
+                        impl<'de> serde::de::Visitor<'de> for EnumVisitor {
+                            type Value = #enum_name;
+                            fn expecting(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+                                f.write_str("variant name or raw value")
+                            }
+                            fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<Self::Value, E> {
+                                match value {
+                                    #( #serde_name_strings => Ok(#enum_name::#variant_names), )*
+                                    _ => Err(E::unknown_variant(value, VARIANTS)),
+                                }
+                            }
+                            fn visit_u64<E: serde::de::Error>(self, value: u64) -> Result<Self::Value, E> {
+                                if value > #max_value {
+                                    return Err(E::invalid_value(serde::de::Unexpected::Unsigned(value), &self));
+                                }
+                                #raw_value_to_result
+                            }
+                            fn visit_i64<E: serde::de::Error>(self, value: i64) -> Result<Self::Value, E> {
+                                match u64::try_from(value) {
+                                    Ok(value) => self.visit_u64(value),
+                                    Err(_) => Err(E::invalid_value(serde::de::Unexpected::Signed(value), &self)),
+                                }
+                            }
+                        }
+                        if deserializer.is_human_readable() {
+                            deserializer.deserialize_any(EnumVisitor)
+                        } else {
+                            deserializer.deserialize_u64(EnumVisitor)
+                        }
+                    }
+                }
+            };
+        }
+    } else {
+        quote! {}
+    };
+
+    // defmt prints the name of the variant
+    let defmt_implementation = if defmt {
+        let variant_names: Vec<&Ident> = emitted_variants.iter().map(|(_, name)| *name).collect();
+        let variant_name_strings: Vec<String> =
+            variant_names.iter().map(|name| name.to_string()).collect();
+        let format_fallback = fallback.as_ref().map(|fallback| {
+            let format_string = format!("{}({{}})", fallback);
+            quote! { Self::#fallback(value) => defmt::write!(f, #format_string, (*value) #bounded_getter), }
+        });
+        quote! {
+            impl defmt::Format for #enum_name {
+                fn format(&self, f: defmt::Formatter) {
+                    match self {
+                        #( Self::#variant_names => defmt::write!(f, #variant_name_strings), )*
+                        #format_fallback
+                    }
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    // All variants in the order of declaration. The fallback variant stands for many values, so it
+    // isn't part of the list
+    let variant_count = emitted_variants.len();
+    let all_variants: Vec<&Ident> = emitted_variants.iter().map(|(_, name)| *name).collect();
+    let variant_enumeration = quote! {
+        /// The number of variants (excluding a fallback variant)
+        pub const COUNT: usize = #variant_count;
+
+        /// All variants (excluding a fallback variant), in the order of declaration
+        pub const ALL: [Self; #variant_count] = [#( Self::#all_variants ),*];
+
+        /// Returns the variant at the given position in ALL, or None if the index is out of bounds.
+        /// This allows iterating over all variants in const functions
+        pub const fn variant(index: usize) -> Option<Self> {
+            if index < Self::COUNT {
+                Some(Self::ALL[index])
+            } else {
+                None
+            }
+        }
+
+        /// Returns an iterator over all variants (excluding a fallback variant), in the order of declaration
+        pub fn iter() -> core::array::IntoIter<Self, #variant_count> {
+            IntoIterator::into_iter(Self::ALL)
+        }
+    };
+
+    // As the fallback variant holds a value, the enum can't be cast to an integer. Instead, the
+    // values are only used in raw_value() and new_with_raw_value()
+    let raw_value_function = match &fallback {
+        Some(fallback) => {
+            let (values, names): (Vec<TokenStream2>, Vec<&Ident>) = emitted_variants
+                .iter()
+                .map(|(value, name)| {
+                    let value = match value {
+                        VariantValue::Known(value) => {
+                            Literal::u128_unsuffixed(*value).into_token_stream()
+                        }
+                        VariantValue::Computed(expression) => {
+                            quote! { (#expression as #base_data_type) }
+                        }
+                    };
+                    (value, *name)
+                })
+                .unzip();
+            quote! {
+                /// Returns the underlying raw value of this bitfield
+                pub const fn raw_value(self) -> #bounded_data_type {
+                    match self {
+                        #( Self::#names => #result_constructor(#values), )*
+                        Self::#fallback(value) => value,
+                    }
+                }
+            }
+        }
+        None => quote! {
+            /// Returns the underlying raw value of this bitfield
+            pub const fn raw_value(self) -> #bounded_data_type { #result_constructor(self as #base_data_type) }
+        },
+    };
+
+    // #[name] is only meant for this macro, so it is removed
+    let variant_patterns: Vec<TokenStream2> = variants
+        .iter()
+        .map(|variant| {
+            let name = &variant.ident;
+            if Some(name) == fallback.as_ref() {
+                quote! { Self::#name(_) }
+            } else {
+                quote! { Self::#name }
+            }
+        })
+        .collect();
+    let variants: Punctuated<Variant, Token![,]> = variants
+        .into_iter()
+        .map(|mut variant| {
+            variant.attrs.retain(|attr| !attr.path.is_ident("name"));
+            if fallback.is_some() {
+                variant.discriminant = None;
+            }
+            variant
+        })
+        .collect();
+
+    let display_implementation = if display {
+        // The fallback variant also shows its raw value, e.g. "Unknown(5)"
+        let body = match &fallback {
+            Some(fallback) => quote! {
+                match self {
+                    Self::#fallback(value) => core::write!(f, "{}({})", self.name(), value),
+                    _ => f.write_str(self.name()),
+                }
+            },
+            None => quote! { f.write_str(self.name()) },
+        };
+        quote! {
+            impl core::fmt::Display for #enum_name {
+                fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                    #body
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    // FromStr accepts the names of all variants except for the fallback variant
+    let name_function = if name_function {
+        quote! {
+            /// Returns the name of the variant
+            pub const fn name(self) -> &'static str {
+                match self {
+                    #( #variant_patterns => #variant_name_strings, )*
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    let from_str_implementation = if let Some(case_insensitive) = from_str {
+        let error_name = format_ident!("Parse{}Error", enum_name);
+        let error_doc = format!(
+            "The error returned by {}::from_str() if a string isn't the name of a variant",
+            enum_name
+        );
+        let error_message = format!("unknown variant of {}", enum_name);
+        let comparison = if case_insensitive {
+            quote! { variant.name().eq_ignore_ascii_case(s) }
+        } else {
+            quote! { variant.name() == s }
+        };
+        quote! {
+            #[doc = #error_doc]
+            #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+            #enum_vis struct #error_name;
+
+            impl core::fmt::Display for #error_name {
+                fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                    f.write_str(#error_message)
+                }
+            }
+
+            impl core::str::FromStr for #enum_name {
+                type Err = #error_name;
+
+                fn from_str(s: &str) -> Result<Self, Self::Err> {
+                    Self::iter().find(|variant| #comparison).ok_or(#error_name)
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
     let expanded = quote! {
         #[derive(Copy, Clone)]
         #( #enum_attrs )*
@@ -224,13 +781,24 @@ pub fn bitenum(args: TokenStream, input: TokenStream) -> TokenStream {
             #variants
         }
 
+        #( #const_assertions )*
+
         impl #enum_name {
-            /// Returns the underlying raw value of this bitfield
-            pub const fn raw_value(self) -> #bounded_data_type { #result_constructor(self as #base_data_type) }
+            #raw_value_function
 
             #constructor_function
+
+            #variant_enumeration
+
+            #name_function
         }
+
+        #display_implementation
+        #from_str_implementation
+
+        #serde_implementations
+        #defmt_implementation
     };
     //println!("Expanded: {}", expanded.to_string());
-    TokenStream::from(expanded)
+    Ok(expanded)
 }
@@ -3,18 +3,24 @@ use std::ops::{Deref, Range};
 use std::str::FromStr;
 
 use proc_macro2::TokenTree;
-use quote::{quote, ToTokens};
+use quote::{format_ident, quote, quote_spanned, ToTokens};
 use syn::__private::TokenStream2;
-use syn::{Attribute, Data, DeriveInput, GenericArgument, PathArguments, Type};
+use syn::ext::IdentExt;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{Attribute, Data, DeriveInput, GenericArgument, LitInt, PathArguments, Token, Type};
+
+use crate::arguments::{parse_arguments, unique_arguments, Argument, Errors};
 
 /// Returns true if the number can be expressed by a regular data type like u8 or u32.
 /// 1 is also true, as it can be expressed as a bool
 fn is_int_size_regular_type(size: usize) -> bool {
-    return size == 1 || size == 8 || size == 16 || size == 32 || size == 64 || size == 128;
+    size == 1 || size == 8 || size == 16 || size == 32 || size == 64 || size == 128
 }
 
 fn parse_arbitrary_int_type(s: &str) -> Result<usize, ()> {
-    if !s.starts_with("u") || s.len() < 2 {
+    if !s.starts_with('u') || s.len() < 2 {
         return Err(());
     }
 
@@ -27,251 +33,685 @@ fn parse_arbitrary_int_type(s: &str) -> Result<usize, ()> {
                 Err(())
             }
         }
-        Err(_) => return Err(()),
+        Err(_) => Err(()),
     }
 }
 
-pub fn bitfield(args: TokenStream, input: TokenStream) -> TokenStream {
-    let args: Vec<_> = proc_macro2::TokenStream::from(args).into_iter().collect();
+/// Parses signed types like i3 or i12, which don't exist in Rust. The getter sign-extends them into
+/// the next larger regular signed type and the setter truncates
+fn parse_arbitrary_signed_int_type(s: &str) -> Result<usize, ()> {
+    if !s.starts_with('i') || s.len() < 2 {
+        return Err(());
+    }
 
-    if args.len() < 1 {
-        panic!(
-            "bitfield! No arguments given, but need at least base data type (e.g. 'bitfield(u32)')"
-        );
+    match usize::from_str(s.split_at(1).1) {
+        Ok(size) if size > 1 && size < 128 && !is_int_size_regular_type(size) => Ok(size),
+        _ => Err(()),
     }
+}
 
-    // Parse arguments: the first argument is required and has the base data type. Further arguments are
-    // optional and are key:value pairs
-    let base_data_type = &args[0];
-    let mut default_value: Option<TokenStream2> = None;
+/// Returns the number of bits of the smallest regular data type (u8, u16 etc) that can hold the given size
+fn regular_type_size_for(size: usize) -> usize {
+    match size {
+        0..=8 => 8,
+        9..=16 => 16,
+        17..=32 => 32,
+        33..=64 => 64,
+        _ => 128,
+    }
+}
+
+/// Creates the identifier of a generated constant, e.g. TYPE_MASK for the field r#type. Errors are
+/// reported at the given span, which is usually the field
+fn constant_ident(name: &str, span: proc_macro2::Span) -> syn::Result<syn::Ident> {
+    syn::parse_str::<syn::Ident>(name).map_err(|_| {
+        syn::Error::new(
+            span,
+            format!(
+                "{} is not a valid name for a generated constant. Rename the field",
+                name
+            ),
+        )
+    })
+}
+
+/// A bit index like 5 or an inclusive bit range like 1..=8. The tokens are kept for error messages
+struct BitRange {
+    start: usize,
+    end: usize,
+    is_range: bool,
+    tokens: TokenStream2,
+}
 
-    enum ArgumentType {
-        Default,
+impl Parse for BitRange {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let expected =
+            "Expected bit index or inclusive bit range, e.g. bit(5, rw) or bits(1..=8, rw)";
+        let start_literal: LitInt = input
+            .parse()
+            .map_err(|error| syn::Error::new(error.span(), expected))?;
+        let start = start_literal.base10_parse()?;
+        if input.peek(Token![..=]) {
+            let dots: Token![..=] = input.parse()?;
+            let end_literal: LitInt = input
+                .parse()
+                .map_err(|error| syn::Error::new(error.span(), expected))?;
+            Ok(BitRange {
+                start,
+                end: end_literal.base10_parse()?,
+                is_range: true,
+                tokens: quote! { #start_literal #dots #end_literal },
+            })
+        } else if input.peek(Token![..]) {
+            Err(input.error("Expected inclusive range, e.g. bits(1..=8, rw)"))
+        } else {
+            Ok(BitRange {
+                start,
+                end: start,
+                is_range: false,
+                tokens: start_literal.to_token_stream(),
+            })
+        }
     }
-    let mut next_expected: Option<ArgumentType> = None;
+}
 
-    fn handle_next_expected(
-        next_expected: &Option<ArgumentType>,
-        default_value: &mut Option<TokenStream2>,
-        token_stream: TokenStream2,
-    ) {
-        match next_expected {
-            None => panic!("bitfield!: Seen {}, but didn't expect anything. Example of valid syntax: #[bitfield(u32, default: 0)]", token_stream.to_string()),
-            Some(ArgumentType::Default) => {
-                *default_value = Some(token_stream);
-            }
+impl std::fmt::Display for BitRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_range {
+            write!(f, "{}..={}", self.start, self.end)
+        } else {
+            write!(f, "{}", self.start)
         }
     }
-    for i in 1..args.len() {
-        match &args[i] {
-            TokenTree::Punct(p) => match p.to_string().as_str() {
-                "," => next_expected = None,
-                ":" => {}
-                _ => panic!(
-                    "bitfield!: Expected ',' or ':' in argument list. Seen '{}'",
-                    p.to_string()
-                ),
-            },
-            TokenTree::Ident(sym) => {
-                if next_expected.is_some() {
-                    // We might end up here if we refer to a constant, like 'default: SOME_CONSTANT'
-                    handle_next_expected(&next_expected, &mut default_value, sym.to_token_stream());
-                } else {
-                    match sym.to_string().as_str() {
-                        "default" => {
-                            if default_value.is_some() {
-                                panic!("bitfield!: default must only be specified at most once");
-                            }
-                            next_expected = Some(ArgumentType::Default)
+}
+
+/// The position of a field: a single bit range or, for split fields, a list of bit ranges
+enum FieldPosition {
+    Single(BitRange),
+    List(Vec<BitRange>, proc_macro2::Span),
+}
+
+/// The arguments of bit(...) and bits(...), e.g. bits(1..=8, rw, stride: 8)
+struct FieldArguments {
+    position: FieldPosition,
+    // Read/write specifier like rw or reserved: 0
+    access: Argument,
+    named_arguments: Vec<Argument>,
+}
+
+impl Parse for FieldArguments {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let position = if input.peek(syn::token::Bracket) {
+            let content;
+            let brackets = syn::bracketed!(content in input);
+            let ranges = Punctuated::<BitRange, Token![,]>::parse_terminated(&content)?;
+            FieldPosition::List(ranges.into_iter().collect(), brackets.span)
+        } else {
+            FieldPosition::Single(input.parse()?)
+        };
+        if input.is_empty() {
+            return Err(input.error("Expected read/write specifier, e.g. bits(1..=8, rw). Supported read/write specifiers: rw, w, r"));
+        }
+        input.parse::<Token![,]>()?;
+        let access = input.parse()?;
+        let named_arguments = parse_arguments(input)?;
+        Ok(FieldArguments {
+            position,
+            access,
+            named_arguments,
+        })
+    }
+}
+
+/// The arguments of #[bitfield(...)]: the base data type, followed by optional arguments
+struct BitfieldArguments {
+    base_data_type: Type,
+    arguments: Vec<Argument>,
+}
+
+impl Parse for BitfieldArguments {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.is_empty() {
+            return Err(input.error(
+                "No arguments given, but need at least base data type (e.g. 'bitfield(u32)')",
+            ));
+        }
+        Ok(BitfieldArguments {
+            base_data_type: input.parse()?,
+            arguments: parse_arguments(input)?,
+        })
+    }
+}
+
+pub fn bitfield(args: TokenStream, input: TokenStream) -> TokenStream {
+    match generate(args.into(), input.into()) {
+        Ok(expanded) => TokenStream::from(expanded),
+        Err(errors) => TokenStream::from(errors.to_compile_error()),
+    }
+}
+
+fn generate(args: TokenStream2, input: TokenStream2) -> syn::Result<TokenStream2> {
+    let BitfieldArguments {
+        base_data_type,
+        arguments,
+    } = syn::parse2(args)?;
+    // Errors are collected, so that all of them can be reported at once
+    let mut errors = Errors::default();
+
+    // Parse arguments: the first argument is required and has the base data type. Further arguments are
+    // optional and are either key:value pairs or flags
+    let mut default_value: Option<TokenStream2> = None;
+    let mut bit_order_value: Option<TokenStream2> = None;
+    let mut serde_value: Option<TokenStream2> = None;
+    let mut debug = false;
+    let mut defmt = false;
+    let mut register = false;
+    let mut in_place_setters = false;
+    let mut flags = false;
+    let mut builder = false;
+
+    for argument in unique_arguments(&arguments, &mut errors) {
+        match argument.name.to_string().as_str() {
+            "default" => default_value = argument.expect_value(&mut errors).cloned(),
+            "bit_order" => bit_order_value = argument.expect_value(&mut errors).cloned(),
+            "serde" => serde_value = argument.expect_value(&mut errors).cloned(),
+            name => {
+                argument.expect_flag(&mut errors);
+                match name {
+                    "debug" => debug = true,
+                    "defmt" => {
+                        if !cfg!(feature = "defmt") {
+                            errors.add(argument.name.span(), "defmt requires the 'defmt' feature of bitbybit");
                         }
-                        _ => panic!(
-                            "bitfield!: Unexpected argument {}. Supported: 'default'",
-                            sym.to_string()
-                        ),
+                        defmt = true
                     }
+                    "register" => register = true,
+                    "in_place_setters" => in_place_setters = true,
+                    "flags" => flags = true,
+                    "builder" => builder = true,
+                    _ => errors.add(argument.name.span(), format!(
+                        "Unexpected argument {}. Supported: 'default', 'bit_order', 'debug', 'defmt', 'register', 'in_place_setters', 'flags', 'builder', 'serde'",
+                        name
+                    )),
                 }
             }
-            TokenTree::Literal(literal) => {
-                // We end up here if we see a literal, like 'default: 0x1234'
-                handle_next_expected(
-                    &next_expected,
-                    &mut default_value,
-                    literal.to_token_stream(),
+        }
+    }
+
+    // Bit numbering is LSB0 unless specified otherwise. With MSB0, bit 0 is the most significant bit
+    // of the base data type. Ranges are converted to LSB0 right after parsing, so that everything
+    // further down only has to deal with LSB0
+    let is_msb0 = match bit_order_value {
+        None => false,
+        Some(value) => match value.to_string().as_str() {
+            "lsb0" => false,
+            "msb0" => true,
+            s => {
+                errors.add_spanned(
+                    &value,
+                    format!("Unhandled bit_order '{}'. Expected 'lsb0' or 'msb0'", s),
                 );
+                false
             }
-            _ => {
-                panic!("bitfield!: Unexpected token. Example of valid syntax: #[bitfield(u32, default: 0)]")
-            }
-        }
+        },
+    };
+
+    // Bitfields can be serialized as raw value or as a map of the values of their fields
+    enum SerdeMode {
+        Raw,
+        Fields,
     }
+    let serde_mode = match serde_value {
+        None => None,
+        Some(value) if !cfg!(feature = "serde") => {
+            errors.add_spanned(&value, "serde requires the 'serde' feature of bitbybit");
+            None
+        }
+        Some(value) => match value.to_string().as_str() {
+            "raw" => Some(SerdeMode::Raw),
+            "fields" => Some(SerdeMode::Fields),
+            s => {
+                errors.add_spanned(
+                    &value,
+                    format!("Unhandled serde mode '{}'. Expected 'raw' or 'fields'", s),
+                );
+                None
+            }
+        },
+    };
 
-    let base_data_size = match base_data_type.to_string().as_str() {
-        "u8" => 8,
-        "u16" => 16,
-        "u32" => 32,
-        "u64" => 64,
-        "u128" => 128,
-        _ => panic!("bitfield!: Supported values for base data type are u8, u16, u32, u64, u128. {} is invalid", base_data_type.to_string().as_str())
+    // The base data type is either an integer like u32 or an array of integers like [u8; 32], which
+    // allows for bitfields larger than 128 bits. With arrays, element 0 holds the least significant bits
+    let mut base_array: Option<(TokenStream2, usize)> = None;
+    let base_data_size = match &base_data_type {
+        Type::Array(array) => {
+            let element_type = array.elem.to_token_stream();
+            let element_size = match element_type.to_string().as_str() {
+                "u8" => 8,
+                "u16" => 16,
+                "u32" => 32,
+                "u64" => 64,
+                _ => return Err(syn::Error::new_spanned(&array.elem, format!("Supported element types for array base data types are u8, u16, u32, u64. {} is invalid", element_type))),
+            };
+            let length = array.len.to_token_stream().to_string().parse::<usize>().map_err(|_| {
+                syn::Error::new_spanned(&array.len, format!("Array length {} is not a valid number", array.len.to_token_stream()))
+            })?;
+            if length == 0 {
+                return Err(syn::Error::new_spanned(&array.len, "Array base data type needs at least one element"));
+            }
+            base_array = Some((element_type, element_size));
+            element_size * length
+        }
+        _ => match base_data_type.to_token_stream().to_string().as_str() {
+            "u8" => 8,
+            "u16" => 16,
+            "u32" => 32,
+            "u64" => 64,
+            "u128" => 128,
+            // Arbitrary ints like u5 are mostly useful for bitfields that are nested within other bitfields
+            s if parse_arbitrary_int_type(s).is_ok() => parse_arbitrary_int_type(s).unwrap(),
+            s => return Err(syn::Error::new_spanned(&base_data_type, format!("Supported values for base data type are u8, u16, u32, u64, u128, arbitrary ints like u5 or arrays like [u8; 32]. {} is invalid", s))),
+        },
+    };
+    // Bitfields with an arbitrary int base data type are stored in the next larger regular type, which
+    // can be shifted and masked like any other int
+    let is_arbitrary_base = base_array.is_none() && !is_int_size_regular_type(base_data_size);
+    let storage_data_type = if is_arbitrary_base {
+        TokenStream2::from_str(format!("u{}", regular_type_size_for(base_data_size)).as_str())
+            .unwrap()
+    } else {
+        base_data_type.to_token_stream()
+    };
+    // Type of raw_value(), which is also used for masks. For arbitrary ints, values of the storage type
+    // are wrapped
+    let raw_value_data_type = if is_arbitrary_base {
+        quote! { arbitrary_int::#base_data_type }
+    } else {
+        storage_data_type.clone()
+    };
+    let to_raw_value_data_type = |value: TokenStream2| -> TokenStream2 {
+        if is_arbitrary_base {
+            quote! { arbitrary_int::#base_data_type::new(#value) }
+        } else {
+            value
+        }
+    };
+    // Fields are extracted by shifting and masking a value of this type. For arrays, the bits of each
+    // field are gathered into an u128 first
+    let (working_data_type, working_data_size) = if base_array.is_some() {
+        (quote! { u128 }, 128)
+    } else {
+        (
+            storage_data_type.clone(),
+            regular_type_size_for(base_data_size),
+        )
     };
 
-    let input = syn::parse_macro_input!(input as DeriveInput);
+    let input = syn::parse2::<DeriveInput>(input)?;
     let struct_name = input.ident;
     let struct_vis = input.vis;
     let struct_attrs = input.attrs;
 
     let fields = match input.data {
         Data::Struct(struct_data) => struct_data.fields,
-        _ => panic!("bitfield!: Must be used on struct"),
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &struct_name,
+                "bitfield must be used on a struct",
+            ))
+        }
+    };
+
+    // Combines bits (e.g. of reserved fields) into a mask and the values of the bits within the mask.
+    // Both are arrays for array based bitfields and plain integers otherwise
+    let chunk_size = base_array
+        .as_ref()
+        .map_or(base_data_size, |(_, element_size)| *element_size);
+    let to_raw_value_literal = |chunks: &[u128]| -> TokenStream2 {
+        let chunks: Vec<syn::LitInt> = chunks
+            .iter()
+            .map(|chunk| {
+                syn::LitInt::new(
+                    format!("0x{:X}", chunk).as_str(),
+                    proc_macro2::Span::call_site(),
+                )
+            })
+            .collect();
+        if base_array.is_some() {
+            quote! { [#( #chunks ),*] }
+        } else {
+            let chunk = &chunks[0];
+            quote! { #chunk }
+        }
+    };
+    let to_mask_and_value = |bits: &[(usize, bool)]| -> (TokenStream2, TokenStream2) {
+        let mut mask = vec![0u128; base_data_size / chunk_size];
+        let mut value = vec![0u128; base_data_size / chunk_size];
+        for (bit, bit_value) in bits {
+            mask[bit / chunk_size] |= 1u128 << (bit % chunk_size);
+            if *bit_value {
+                value[bit / chunk_size] |= 1u128 << (bit % chunk_size);
+            }
+        }
+        (to_raw_value_literal(&mask), to_raw_value_literal(&value))
+    };
+    // Body of a function that returns raw_value with all bits within mask replaced by value
+    let apply_mask_and_value = |mask: TokenStream2, value: TokenStream2| -> TokenStream2 {
+        match &base_array {
+            Some((_, element_size)) => {
+                let element_count = base_data_size / element_size;
+                quote! {
+                    let mut i = 0;
+                    while i < #element_count {
+                        raw_value[i] = (raw_value[i] & !#mask[i]) | #value[i];
+                        i += 1;
+                    }
+                    raw_value
+                }
+            }
+            None => quote! { (raw_value & !#mask) | #value },
+        }
+    };
+
+    // Turns an inclusive range as written in bits(start..=end) or bit(start) into an LSB0 range
+    let to_lsb0_range = |start: usize, end: usize| -> Range<usize> {
+        if is_msb0 {
+            Range {
+                start: base_data_size - 1 - end,
+                end: base_data_size - start,
+            }
+        } else {
+            Range {
+                start,
+                end: end + 1,
+            }
+        }
     };
-    let accessors: Vec<TokenStream2> = fields.iter().map(|field| {
-        let field_name = field.ident.as_ref().unwrap();
+    // Everything that is generated for a single field
+    struct FieldOutput {
+        name: String,
+        ident: syn::Ident,
+        serialize_field: TokenStream2,
+        // Name and code that deserializes the field from a MapAccess
+        deserialize_field: Option<(String, TokenStream2)>,
+        is_array: bool,
+        is_option_type: bool,
+        // The bits that are used by the field, including every element of arrays
+        occupied_bits: Vec<Range<usize>>,
+        overlaps: Vec<syn::Ident>,
+        accessors: TokenStream2,
+        debug_field: TokenStream2,
+        // Name and value of readable fields, which are printed by defmt::Format
+        defmt_field: Option<(String, TokenStream2)>,
+        readable: bool,
+        reserved_bits: Vec<(usize, bool)>,
+        side_effect_bits: Vec<(usize, bool)>,
+        is_read_clear: bool,
+        // Bit of single bit fields, which become constants with flags
+        flag_bit: Option<usize>,
+        // Setter name, setter type and number of elements of writable fields. Used by the builder
+        writable: Option<(syn::Ident, Type, Option<usize>)>,
+        // 'default' argument and an expression that applies the default value to raw_value
+        default_value: Option<(syn::Ident, TokenStream2)>,
+        // Functions and constants generated for the field
+        generated_names: Vec<syn::Ident>,
+        // Checks that are evaluated at compile time, outside of the impl block
+        const_assertions: TokenStream2,
+    }
+    // Fields with errors are skipped, so that the errors of all fields are reported at once
+    let field_outputs: Vec<FieldOutput> = fields.iter().map(|field| -> syn::Result<FieldOutput> {
+        let field_name = field.ident.as_ref().ok_or_else(|| syn::Error::new_spanned(field, "bitfield fields must be named"))?;
+        // Names of the functions and constants that are generated for this field
+        let mut generated_names: Vec<syn::Ident> = Vec::new();
         let (ty, indexed_count) = {
             match &field.ty {
                 Type::Array(ty) => {
                     let length = (&ty.len).into_token_stream().to_string();
 
-                    (ty.elem.deref(), Some(length.parse::<usize>().expect(format!("{} is not a valid number", length).as_str())))
+                    (ty.elem.deref(), Some(length.parse::<usize>().map_err(|_| syn::Error::new_spanned(&ty.len, format!("{} is not a valid number", length)))?))
                 }
                 _ => (&field.ty, None)
             }
         };
+        // For signed types, this is the number of bits of the signed value (e.g. 12 for i12)
+        let mut signed_size: Option<usize> = None;
         let field_type_size_from_data_type = match ty {
             Type::Path(path) => {
-                match path.to_token_stream().to_string().as_str() {
+                let type_string = path.to_token_stream().to_string();
+                if let Some(size) = match type_string.as_str() {
+                    "i8" => Some(8),
+                    "i16" => Some(16),
+                    "i32" => Some(32),
+                    "i64" => Some(64),
+                    "i128" => Some(128),
+                    s => parse_arbitrary_signed_int_type(s).ok(),
+                } {
+                    signed_size = Some(size);
+                }
+                match type_string.as_str() {
                     "bool" => Some(1),
                     "u8" | "i8" => Some(8),
                     "u16" | "i16" => Some(16),
                     "u32" | "i32" => Some(32),
                     "u64" | "i64" => Some(64),
                     "u128" | "i128" => Some(128),
-                    "u1" => panic!("bitfield!: Field {} has datatype u1, which is not supported. Use bool instead", field_name),
+                    "u1" => return Err(syn::Error::new_spanned(ty, format!("Field {} has datatype u1, which is not supported. Use bool instead", field_name))),
                     s if parse_arbitrary_int_type(s).is_ok() => Some(parse_arbitrary_int_type(s).unwrap()),
-                    _ => None, // Enum type - size is the the number of bits
+                    s if parse_arbitrary_signed_int_type(s).is_ok() => Some(parse_arbitrary_signed_int_type(s).unwrap()),
+                    _ => None, // Enum or nested bitfield - size is the the number of bits
                 }
             }
-            _ => panic!("bitfield!: Field type {} not valid. bool, u8, i8, u16, i16, u32, i32, u64, i64, u128, i128, arbitrary int (e.g. u3, u62). Their arrays are also supported", ty.into_token_stream()),
+            _ => return Err(syn::Error::new_spanned(ty, format!("Field type {} not valid. bool, u8, i8, u16, i16, u32, i32, u64, i64, u128, i128, arbitrary int (e.g. u3, u62, i12). Their arrays are also supported", ty.into_token_stream()))),
         };
         let mut range: Option<Range<usize>> = None;
+        // For fields that are split over several bit ranges: The pieces, starting with the one that
+        // holds the most significant bits of the value
+        let mut split_pieces: Option<Vec<Range<usize>>> = None;
         let mut provide_getter = false;
         let mut provide_setter = false;
         let mut indexed_stride: Option<usize> = None;
+        // Bounded index type of arrays (e.g. u4 for 16 elements) and its number of bits. Arrays are
+        // indexed by usize unless specified otherwise
+        let mut index_type: Option<(syn::Ident, usize)> = None;
+        // Value of the field when the bitfield is created via new()
+        let mut field_default: Option<(syn::Ident, TokenStream2)> = None;
+        // Reserved fields don't have accessors. Instead, their bits must always have the given value
+        let mut reserved_value: Option<u128> = None;
+        // Hardware side effects: write 1 to clear, write 0 to clear, write 1 to set or clear on read
+        enum SideEffect {
+            W1c,
+            W0c,
+            W1s,
+            Rc,
+        }
+        let mut side_effect: Option<SideEffect> = None;
+        // Other fields that this field is allowed to overlap with (e.g. for alternative views on the same bits)
+        let mut overlaps: Vec<syn::Ident> = Vec::new();
 
         let mut doc_comment: Option<&Attribute> = None;
 
         for attr in &field.attrs {
-            let attr_name = attr.path.segments.first().unwrap_or_else(|| panic!("bitfield!: Invalid path")).ident.to_string();
+            let attr_name = match attr.path.get_ident() {
+                Some(ident) => ident.to_string(),
+                None => return Err(syn::Error::new_spanned(&attr.path, "Unhandled attribute. Only supported attributes are 'bit' or 'bits'")),
+            };
             match attr_name.as_str() {
                 "bits" | "bit" => {
                     let is_range = attr_name.as_str() == "bits";
 
                     if range.is_some() {
-                        panic!("bitfield!: Only one 'bit' or 'bits' is supported per field");
-                    }
-                    let attr_token_string = attr.tokens.to_string().clone();
-                    if &attr_token_string[..1] != "(" {
-                        panic!("bitfield!: Expected '(' after '{}'", attr_name.as_str());
-                    }
-                    if &attr_token_string[attr_token_string.len() - 1..] != ")" {
-                        panic!("bitfield!: Expected ')' to close '{}'", attr_name.as_str());
-                    }
-                    let arguments_string = &attr_token_string[1..attr_token_string.len() - 1];
-                    let arguments: Vec<&str> = arguments_string.split(",").map(|s| s.trim()).collect();
-
-                    if arguments.len() < 2 {
-                        if is_range {
-                            panic!("bitfield!: Expected inclusive bit-range and read/write specifier, e.g. bits(1..=8, rw). Supported read/write specifiers: rw, w, r.")
-                        } else {
-                            panic!("bitfield!: Expected bit index and read/write specifier, e.g. bit(5, rw). Supported read/write specifiers: rw, w, r.")
-                        }
+                        return Err(syn::Error::new_spanned(attr, "Only one 'bit' or 'bits' is supported per field"));
                     }
+                    let arguments: FieldArguments = attr.parse_args()?;
 
                     // *** Parse first argument:
                     //   inclusive range like "6..=10" if attr_name = "bits"
+                    //   list of bits and ranges like "[31, 7, 30..=25]" if attr_name = "bits"
                     //   single bit "6" if attr_name = "bit"
-                    if is_range {
-                        let range_elements: Vec<&str> = arguments[0].split("..").map(|s| s.trim()).collect();
-                        if range_elements.len() != 2 {
-                            panic!("bitfield!: Expected valid range, e.g. bits(1..=8, rw)");
-                        }
-                        let start = range_elements[0].parse::<usize>().unwrap_or_else(|x| panic!("bitfield!: Expected valid range, e.g. 1..=8 but '{}' is not a number", x));
-                        let end_string = range_elements[1];
-                        if &end_string[0..1] != "=" {
-                            panic!("bitfield!: Expected inclusive range, e.g. bits(1..=8, rw)'");
-                        }
-
-                        let end = end_string[1..].trim().parse::<usize>().unwrap_or_else(|x| panic!("bitfield!: Expected valid range, e.g. 1..=8 but '{}' is not a number", x));
-                        if start > end {
-                            panic!("bitfield!: In Range {}..={}, start is not <= end", start, end);
-                        }
-                        if start >= base_data_size {
-                            panic!("bitfield!: In Range {}..={}, start is out of range, as the base type has {} bits", start, end, base_data_size);
+                    match arguments.position {
+                        FieldPosition::List(_, span) if !is_range => {
+                            return Err(syn::Error::new(span, "Expected bit index, e.g. bit(5, rw). For a list of bit ranges, use bits([7, 3..=0], rw)"));
                         }
-                        if end >= base_data_size {
-                            panic!("bitfield!: In Range {}..={}, end is out of range, as the base type has {} bits", start, end, base_data_size);
+                        FieldPosition::List(list, span) => {
+                            let mut pieces: Vec<Range<usize>> = Vec::new();
+                            for piece in &list {
+                                // Within a list, ranges can be written in either direction (e.g. 30..=25)
+                                let (start, end) = (piece.start.min(piece.end), piece.start.max(piece.end));
+                                if end >= base_data_size {
+                                    return Err(syn::Error::new_spanned(&piece.tokens, format!("Bit range {} is out of range, as the base type has {} bits", piece, base_data_size)));
+                                }
+                                let piece_range = to_lsb0_range(start, end);
+                                if pieces.iter().any(|p| p.start < piece_range.end && piece_range.start < p.end) {
+                                    return Err(syn::Error::new_spanned(&piece.tokens, format!("Bit range {} overlaps with another range of the same field", piece)));
+                                }
+                                pieces.push(piece_range);
+                            }
+                            if pieces.len() < 2 {
+                                return Err(syn::Error::new(span, "Expected at least two bit ranges in list. For a single range, use bits(1..=8, rw)"));
+                            }
+                            range = Some(Range { start: 0, end: pieces.iter().map(|p| p.len()).sum() });
+                            split_pieces = Some(pieces);
                         }
-                        if start == end {
-                            panic!("bitfield!: In Range {start}..={end}, start is equal to end. Use the syntax 'bit({start})' instead", start = start, end = end)
+                        FieldPosition::Single(bit_range) if is_range => {
+                            let BitRange { start, end, .. } = bit_range;
+                            let error = |message: String| Err(syn::Error::new_spanned(&bit_range.tokens, message));
+                            if !bit_range.is_range {
+                                return error(format!("Expected inclusive range, e.g. bits(1..=8, rw). For a single bit, use bit({}, rw)", start));
+                            }
+                            if start > end {
+                                return error(format!("In Range {}..={}, start is not <= end", start, end));
+                            }
+                            if start >= base_data_size {
+                                return error(format!("In Range {}..={}, start is out of range, as the base type has {} bits", start, end, base_data_size));
+                            }
+                            if end >= base_data_size {
+                                return error(format!("In Range {}..={}, end is out of range, as the base type has {} bits", start, end, base_data_size));
+                            }
+                            if start == end {
+                                return error(format!("In Range {start}..={end}, start is equal to end. Use the syntax 'bit({start})' instead", start = start, end = end));
+                            }
+                            range = Some(to_lsb0_range(start, end));
                         }
-                        range = Some(Range { start, end: end + 1 });
-                    } else {
-                        let bit_index_string = arguments[0];
-                        let bit_index = bit_index_string.parse::<usize>()
-                            .unwrap_or_else(|x| panic!("bitfield!: Expected valid bit index, e.g. bit(8) but '{}' is not a number: {}", bit_index_string, x));
-                        if bit_index >= base_data_size {
-                            panic!("bitfield!: Bit index {} is out of range", bit_index);
+                        FieldPosition::Single(bit_range) => {
+                            if bit_range.is_range {
+                                return Err(syn::Error::new_spanned(&bit_range.tokens, format!("Expected bit index, e.g. bit(5, rw). For a range, use bits({}, rw)", bit_range)));
+                            }
+                            let bit_index = bit_range.start;
+                            if bit_index >= base_data_size {
+                                return Err(syn::Error::new_spanned(&bit_range.tokens, format!("Bit index {} is out of range, as the base type has {} bits", bit_index, base_data_size)));
+                            }
+                            range = Some(to_lsb0_range(bit_index, bit_index));
                         }
-                        range = Some(Range { start: bit_index, end: bit_index + 1 });
                     }
 
                     // *** Parse second argument: we expect either "r", "w" or "rw"
-                    match arguments[1] {
-                        "rw" => {
-                            provide_getter = true;
-                            provide_setter = true;
+                    let access = &arguments.access;
+                    match access.name.to_string().as_str() {
+                        "reserved" => {
+                            let value = access.value.as_ref().ok_or_else(|| syn::Error::new_spanned(&access.name, "Expected reserved value, e.g. bits(16..=31, reserved: 0)"))?;
+                            reserved_value = Some(syn::parse2::<LitInt>(value.clone()).and_then(|literal| literal.base10_parse::<u128>()).map_err(|_| {
+                                syn::Error::new_spanned(value, format!("Reserved value '{}' of field {} is not a valid unsigned integer", value, field_name))
+                            })?);
                         }
-                        "r" => {
-                            provide_getter = true;
-                            provide_setter = false;
-                        }
-                        "w" => {
-                            provide_getter = false;
-                            provide_setter = true;
+                        specifier => {
+                            if let Some(value) = &access.value {
+                                return Err(syn::Error::new_spanned(value, format!("{} doesn't take a value", specifier)));
+                            }
+                            match specifier {
+                                "rw" => {
+                                    provide_getter = true;
+                                    provide_setter = true;
+                                }
+                                "r" => {
+                                    provide_getter = true;
+                                    provide_setter = false;
+                                }
+                                "w" => {
+                                    provide_getter = false;
+                                    provide_setter = true;
+                                }
+                                "w1c" | "w0c" | "w1s" | "rc" => {
+                                    provide_getter = true;
+                                    provide_setter = false;
+                                    side_effect = Some(match specifier {
+                                        "w1c" => SideEffect::W1c,
+                                        "w0c" => SideEffect::W0c,
+                                        "w1s" => SideEffect::W1s,
+                                        _ => SideEffect::Rc,
+                                    });
+                                }
+                                _ => return Err(syn::Error::new_spanned(&access.name, format!("Unhandled read/write specifier {}. Expected 'r', 'w', 'rw', 'w1c', 'w0c', 'w1s', 'rc' or 'reserved: value'", specifier))),
+                            }
                         }
-                        _ => panic!("bitfield!: Unhandled read/write specifier {}. Expected 'r', 'w', or 'rw'", arguments[1])
                     }
 
-                    // *** Parse additional named arguments (at the moment just stride: X)
-                    for argument_index in 2..arguments.len() {
-                        let argument = &arguments[argument_index];
-                        let argument_elements: Vec<&str> = argument.split(":").map(|s| s.trim()).collect();
-                        if argument_elements.len() != 2 {
-                            panic!("bitfield!: Named arguments have to be in the form of 'argument: value'. Seen: {:?}", argument_elements)
+                    // *** Parse additional named arguments (stride: X and overlaps: other_field). As a
+                    // field can overlap with several others, overlaps can be given more than once
+                    let mut named_argument_errors = Errors::default();
+                    let (overlaps_arguments, other_arguments): (Vec<&Argument>, Vec<&Argument>) = arguments.named_arguments.iter().partition(|argument| argument.name == "overlaps");
+                    for argument in overlaps_arguments {
+                        if let Some(value) = argument.expect_value(&mut named_argument_errors) {
+                            match syn::parse2::<syn::Ident>(value.clone()) {
+                                Ok(other_name) => overlaps.push(other_name),
+                                Err(_) => named_argument_errors.add_spanned(value, "Expected the name of another field, e.g. overlaps: other_field"),
+                            }
                         }
-                        match argument_elements[0] {
+                    }
+                    for argument in unique_arguments(other_arguments, &mut named_argument_errors) {
+                        match argument.name.to_string().as_str() {
                             "stride" => {
                                 if indexed_count.is_none() {
-                                    panic!("bitfield!: stride is only supported for indexed properties. Use array type (e.g. [u8; 8]) to indicate");
+                                    named_argument_errors.add(argument.name.span(), "stride is only supported for indexed properties. Use array type (e.g. [u8; 8]) to indicate");
+                                }
+                                if let Some(value) = argument.expect_value(&mut named_argument_errors) {
+                                    match syn::parse2::<LitInt>(value.clone()).and_then(|literal| literal.base10_parse()) {
+                                        Ok(stride) => indexed_stride = Some(stride),
+                                        Err(_) => named_argument_errors.add_spanned(value, format!("{} is not a number", value)),
+                                    }
+                                }
+                            }
+                            "index" => {
+                                if indexed_count.is_none() {
+                                    named_argument_errors.add(argument.name.span(), "index is only supported for indexed properties. Use array type (e.g. [u8; 8]) to indicate");
+                                }
+                                if let Some(value) = argument.expect_value(&mut named_argument_errors) {
+                                    let bits = syn::parse2::<syn::Ident>(value.clone()).ok().and_then(|ident| {
+                                        let bits = ident.to_string().strip_prefix('u').and_then(|bits| usize::from_str(bits).ok())?;
+                                        if (1..=16).contains(&bits) { Some((ident, bits)) } else { None }
+                                    });
+                                    match bits {
+                                        Some(bits) => index_type = Some(bits),
+                                        None => named_argument_errors.add_spanned(value, format!("Unhandled index type {}. Supported: u1, u2, u3, .., u16", value)),
+                                    }
                                 }
-                                indexed_stride = Some(argument_elements[1].parse().expect(format!("bitfield!: {} is not a number", argument_elements[1]).as_str()))
                             }
-                            _ => panic!("bitfield!: Unhandled named argument '{}'. Supported: 'stride'", argument_elements[0])
+                            "default" => {
+                                if let Some(value) = argument.expect_value(&mut named_argument_errors) {
+                                    field_default = Some((argument.name.clone(), value.clone()));
+                                }
+                            }
+                            name => named_argument_errors.add(argument.name.span(), format!("Unhandled named argument '{}'. Supported: 'stride', 'overlaps', 'index', 'default'", name)),
                         }
                     }
+                    named_argument_errors.check()?;
                 }
                 "doc" => {
                     // inline documentation. pass through to both getter and setter
                     doc_comment = Some(attr);
                 }
                 _ => {
-                    panic!("bitfield!: Unhandled attribute '{}'. Only supported attributes are 'bit' or 'bits'", attr_name);
+                    return Err(syn::Error::new_spanned(&attr.path, format!("Unhandled attribute '{}'. Only supported attributes are 'bit' or 'bits'", attr_name)));
                 }
             }
         };
-
+        // For split fields, the range describes the value after gathering all pieces, which starts at bit 0
         let (lowest_bit, number_of_bits) = match range {
             Some(ref range) => (range.start, range.end - range.start),
-            None => panic!("bitfield!: Expected valid range, e.g. bits(1..=8, rw) or bit(4, r)")
+            None => return Err(syn::Error::new_spanned(field_name, "Expected valid range, e.g. #[bits(1..=8, rw)] or #[bit(4, r)]"))
         };
+        if split_pieces.is_some() && indexed_count.is_some() {
+            return Err(syn::Error::new_spanned(&field.ty, format!("Field {} is split over several bit ranges, which is not supported for arrays", field_name)));
+        }
         let (field_type_size, primitive_type) = match field_type_size_from_data_type {
             None => (number_of_bits, {
                 if number_of_bits <= 8 {
@@ -285,68 +725,163 @@ pub fn bitfield(args: TokenStream, input: TokenStream) -> TokenStream {
                 } else if number_of_bits <= 128 {
                     quote! { u128 }
                 } else {
-                    panic!("bitfield!: number_of_bits is to large!")
+                    return Err(syn::Error::new_spanned(field_name, "number_of_bits is too large"));
                 }
             }),
-            Some(b) => (b, quote! { #ty }),
+            Some(b) => match signed_size {
+                Some(signed_size) if !is_int_size_regular_type(signed_size) => {
+                    let signed_type = TokenStream2::from_str(format!("i{}", regular_type_size_for(signed_size)).as_str()).unwrap();
+                    (b, signed_type)
+                }
+                _ => (b, quote! { #ty }),
+            },
         };
+        // Signed values are converted through the unsigned type of the same size, so that they
+        // don't get sign-extended into neighbouring fields
+        let unsigned_primitive_type = signed_size.map(|signed_size| {
+            TokenStream2::from_str(format!("u{}", regular_type_size_for(signed_size)).as_str()).unwrap()
+        });
+        let is_arbitrary_signed = signed_size.is_some_and(|s| !is_int_size_regular_type(s));
 
         if number_of_bits != field_type_size {
-            panic!("bitfield!: Field {} has type {}, which doesn't match the number of bits ({}) that are being used for it", field_name, ty.to_token_stream().to_string(), number_of_bits);
+            return Err(syn::Error::new_spanned(ty, format!("Field {} has type {}, which doesn't match the number of bits ({}) that are being used for it", field_name, ty.to_token_stream(), number_of_bits)));
         }
 
         // Verify bounds for arrays
-        if indexed_count.is_some() {
+        if let Some(indexed_count) = indexed_count {
             // If stride wasn't given, use the field width
             if indexed_stride.is_none() {
                 indexed_stride = Some(number_of_bits)
             }
 
             if number_of_bits > indexed_stride.unwrap() {
-                panic!("bitfield!: Field {} is declared as {} bits, which is larger than its stride {}", field_name, number_of_bits, indexed_stride.unwrap());
+                return Err(syn::Error::new_spanned(field_name, format!("Field {} is declared as {} bits, which is larger than its stride {}", field_name, number_of_bits, indexed_stride.unwrap())));
+            }
+
+            if indexed_count < 2 {
+                return Err(syn::Error::new_spanned(&field.ty, format!("Field {} is declared as indexing, but with fewer than 2 elements.", field_name)));
             }
 
-            let number_of_bits_indexed = indexed_count.unwrap() * indexed_stride.unwrap() + range.unwrap().start;
-            if base_data_size > base_data_size {
-                panic!("bitfield!: Field {} requires more bits via indexing ({}) than the bitfield has ({})", field_name, number_of_bits_indexed, base_data_size);
+            // A bounded index type has to be able to address every element, but not more
+            if let Some((index_type, index_bits)) = &index_type {
+                if 1usize << index_bits != indexed_count {
+                    let message = if indexed_count.is_power_of_two() {
+                        format!("Field {} has {} elements, so its index type has to be u{}", field_name, indexed_count, indexed_count.trailing_zeros())
+                    } else {
+                        format!("Field {} has {} elements, which can't be addressed by a bounded index type. Use the try_ accessors instead", field_name, indexed_count)
+                    };
+                    return Err(syn::Error::new_spanned(index_type, message));
+                }
             }
 
-            if indexed_count.unwrap() < 2 {
-                panic!("bitfield!: Field {} is declared as indexing, but with fewer than 2 elements.", field_name);
+            // With LSB0, the elements grow towards the most significant bit. With MSB0, they grow
+            // towards the least significant bit (which is the higher MSB0 bit index)
+            let number_of_bits_indexed = (indexed_count - 1) * indexed_stride.unwrap() + number_of_bits;
+            let fits = if is_msb0 {
+                (indexed_count - 1) * indexed_stride.unwrap() <= lowest_bit
+            } else {
+                lowest_bit + number_of_bits_indexed <= base_data_size
+            };
+            if !fits {
+                return Err(syn::Error::new_spanned(&field.ty, format!("Field {} requires more bits via indexing ({}) than the bitfield has ({})", field_name, number_of_bits_indexed, base_data_size)));
             }
         }
-        let one = syn::parse_str::<syn::LitInt>(format!("1u{}", base_data_size).as_str()).unwrap_or_else(|_| panic!("bitfield!: Error parsing one literal"));
+        let one = syn::parse_str::<syn::LitInt>(format!("1u{}", working_data_size).as_str()).map_err(|_| syn::Error::new_spanned(&field.ty, format!("Field {} has no data type with {} bits", field_name, working_data_size)))?;
+
+        // The bits that each element occupies (a single element unless this is an array). For split
+        // fields, the pieces start with the most significant bits of the value
+        let placements: Vec<Vec<Range<usize>>> = match (&split_pieces, indexed_count) {
+            (Some(pieces), _) => vec![pieces.clone()],
+            (None, Some(indexed_count)) => (0..indexed_count).map(|index| {
+                let element_lowest_bit = if is_msb0 {
+                    lowest_bit - index * indexed_stride.unwrap()
+                } else {
+                    lowest_bit + index * indexed_stride.unwrap()
+                };
+                vec![Range { start: element_lowest_bit, end: element_lowest_bit + number_of_bits }]
+            }).collect(),
+            (None, None) => vec![vec![Range { start: lowest_bit, end: lowest_bit + number_of_bits }]],
+        };
+
+        // Bit index and required value of every bit of a reserved field
+        let reserved_bits: Vec<(usize, bool)> = match reserved_value {
+            Some(reserved_value) => {
+                if number_of_bits < 128 && reserved_value >= (1u128 << number_of_bits) {
+                    return Err(syn::Error::new_spanned(field_name, format!("Reserved value {} of field {} doesn't fit into {} bits", reserved_value, field_name, number_of_bits)));
+                }
+                placements.iter().flat_map(|pieces| {
+                    let mut offset = number_of_bits;
+                    pieces.iter().flat_map(move |piece| {
+                        offset -= piece.len();
+                        let piece_offset = offset;
+                        piece.clone().map(move |bit| (bit, (reserved_value >> (piece_offset + bit - piece.start)) & 1 != 0))
+                    }).collect::<Vec<_>>()
+                }).collect()
+            }
+            None => Vec::new(),
+        };
+
+        // Lowest bit of the element at 'index' (only used for arrays)
+        let effective_index = indexed_stride.map(|indexed_stride| {
+            if is_msb0 {
+                quote! { (#lowest_bit - index * #indexed_stride) }
+            } else {
+                quote! { (#lowest_bit + index * #indexed_stride) }
+            }
+        });
+        // Type of the index of array accessors. Bounded index types are turned into an usize first, which
+        // is what all index calculations work with
+        let (index_argument_type, index_to_usize) = match &index_type {
+            None => (quote! { usize }, quote! {}),
+            Some((index_type, 8 | 16)) => (quote! { #index_type }, quote! { let index = index as usize; }),
+            Some((index_type, _)) => (quote! { arbitrary_int::#index_type }, quote! { let index = index.value() as usize; }),
+        };
+        // Turns an usize (e.g. a literal or a loop variable) into an argument for the index of array accessors
+        let index_from_usize = |index: TokenStream2| -> TokenStream2 {
+            match &index_type {
+                None => index,
+                Some((index_type, 8 | 16)) => quote! { (#index as #index_type) },
+                Some((index_type, bits)) => {
+                    let primitive_index_type = TokenStream2::from_str(format!("u{}", regular_type_size_for(*bits)).as_str()).unwrap();
+                    quote! { arbitrary_int::#index_type::new(#index as #primitive_index_type) }
+                }
+            }
+        };
 
-        // If a convert_type is given, that will be the final getter/setter type. If not, it is the base type
+        // If a convert_type is given (enums and nested bitfields), that will be the final getter/setter type.
+        // If not, it is the base type
         enum CustomType {
             No,
             Yes(Type),
         }
+        // Option<T> fields have a getter that returns Result<T, primitive_type>
+        let mut is_option_type = false;
         let (custom_type, getter_type, setter_type) = if field_type_size_from_data_type.is_none() {
             // Test for optional type. We have to disect the Option<T> type to do that
             let (inner_type, result_type) = if let Type::Path(type_path) = ty {
                 if type_path.path.segments.len() != 1 {
-                    panic!("Invalid path segment. Expected Enumeration or Option<Enumeration>");
+                    return Err(syn::Error::new_spanned(ty, "Invalid path segment. Expected Enumeration or Option<Enumeration>"));
                 }
                 let option_segment = type_path.path.segments.first().unwrap();
-                if option_segment.ident.to_string() == "Option" {
+                if option_segment.ident == "Option" {
                     match &option_segment.arguments {
                         PathArguments::AngleBracketed(args) => {
                             if args.args.len() != 1 {
-                                panic!("Invalid Option<T> path. Expected exactly one generic type argument");
+                                return Err(syn::Error::new_spanned(args, "Invalid Option<T> path. Expected exactly one generic type argument"));
                             }
                             let option_generic_type = args.args.first().unwrap();
                             match option_generic_type {
                                 GenericArgument::Type(generic_type) => {
-                                    let result_type_string = format!("Result<{}, {}>", generic_type.to_token_stream().to_string(), primitive_type.to_token_stream().to_string());
-                                    let result_type = syn::parse_str::<syn::Type>(&result_type_string).expect("bitfield!: Error creating type from Result<,>");
+                                    let result_type_string = format!("Result<{}, {}>", generic_type.to_token_stream(), primitive_type);
+                                    let result_type = syn::parse_str::<syn::Type>(&result_type_string).map_err(|_| syn::Error::new_spanned(generic_type, "Invalid Option binding: Expected a type that can be used in Result<T, raw value>"))?;
+                                    is_option_type = true;
 
                                     (generic_type, result_type)
                                 }
-                                _ => panic!("Invalid Option binding: Expected generic type")
+                                _ => return Err(syn::Error::new_spanned(option_generic_type, "Invalid Option binding: Expected generic type")),
                             }
                         }
-                        _ => panic!("Expected < after Option")
+                        _ => return Err(syn::Error::new_spanned(option_segment, "Expected < after Option")),
                     }
                 } else {
                     (ty, ty.clone())
@@ -356,6 +891,9 @@ pub fn bitfield(args: TokenStream, input: TokenStream) -> TokenStream {
             };
 
             (CustomType::Yes(inner_type.clone()), result_type, inner_type.clone())
+        } else if is_arbitrary_signed {
+            let signed_type = syn::parse2::<Type>(primitive_type.clone()).unwrap();
+            (CustomType::No, signed_type.clone(), signed_type)
         } else {
             (CustomType::No, ty.clone(), ty.clone())
         };
@@ -368,55 +906,79 @@ pub fn bitfield(args: TokenStream, input: TokenStream) -> TokenStream {
             },
         };
 
+        // Split fields and fields of array based bitfields are gathered into a single value first,
+        // which then starts at bit 0. Everything else reads straight from raw_value
+        let is_gathered = split_pieces.is_some() || base_array.is_some();
+        // Lowest bit and number of bits of each piece, starting with the most significant bits of the value
+        let pieces: Vec<(TokenStream2, usize)> = match &split_pieces {
+            Some(pieces) => pieces
+                .iter()
+                .map(|piece| {
+                    let piece_lowest_bit = piece.start;
+                    (quote! { #piece_lowest_bit }, piece.len())
+                })
+                .collect(),
+            None => vec![(
+                effective_index.clone().unwrap_or_else(|| quote! { #lowest_bit }),
+                number_of_bits,
+            )],
+        };
+        let (source, gather) = if is_gathered {
+            let mut offset = number_of_bits;
+            let gathered_pieces: Vec<TokenStream2> = pieces.iter().map(|(piece_lowest_bit, piece_number_of_bits)| {
+                offset -= piece_number_of_bits;
+                if base_array.is_some() {
+                    quote! { (Self::__bitbybit_extract_raw_bits(&self.raw_value, #piece_lowest_bit, #piece_number_of_bits) << #offset) }
+                } else {
+                    quote! { (((self.raw_value >> #piece_lowest_bit) & ((#one << #piece_number_of_bits) - #one)) << #offset) }
+                }
+            }).collect();
+            (quote! { gathered }, quote! { let gathered: #working_data_type = #( #gathered_pieces )|*; })
+        } else {
+            (quote! { self.raw_value }, quote! {})
+        };
+        // The bit within source at which the field starts
+        let shift = if is_gathered { quote! { 0 } } else { pieces[0].0.clone() };
+
         let getter =
             if provide_getter {
-                let extracted_bits = if use_regular_int {
+                generated_names.push(field_name.unraw());
+                let extracted_bits = if is_arbitrary_signed {
+                    // Move the field to the top of the next larger signed type, then shift it back
+                    // down. The arithmetic shift takes care of the sign extension
+                    let padding = regular_type_size_for(number_of_bits) - number_of_bits;
+                    quote! {
+                        ((((#source >> #shift) as #unsigned_primitive_type) << #padding) as #primitive_type) >> #padding
+                    }
+                } else if use_regular_int {
                     // Extract standard type (u8, u16 etc)
-                    if indexed_count.is_some() {
-                        let indexed_stride = indexed_stride.unwrap();
-                        if field_type_size == 1 {
-                            quote! { (self.raw_value & (#one << (#lowest_bit + index * #indexed_stride))) != 0 }
-                        } else {
-                            quote! { ((self.raw_value >> (#lowest_bit + index * #indexed_stride)) & ((#one << #number_of_bits) - #one)) as #primitive_type }
-                        }
+                    if field_type_size == 1 {
+                        quote! { (#source & (#one << #shift)) != 0 }
+                    } else if number_of_bits == working_data_size {
+                        // If the field is the whole size of the source, we can't apply a mask
+                        // as that would overflow. However, we don't need to (shift is 0 here)
+                        quote! { #source as #primitive_type }
                     } else {
-                        if field_type_size == 1 {
-                            quote! { (self.raw_value & (#one << #lowest_bit)) != 0 }
-                        } else {
-                            if number_of_bits == base_data_size {
-                                // If the field is the whole size of the bitfield, we can't apply a mask
-                                // as that would overflow. However, we don't need to
-                                assert_eq!(lowest_bit, 0);
-                                quote! { self.raw_value as #primitive_type }
-                            } else {
-                                quote! {
-                                    ((self.raw_value >> #lowest_bit) & ((#one << #number_of_bits) - #one)) as #primitive_type
-                                }
-                            }
+                        quote! {
+                            ((#source >> #shift) & ((#one << #number_of_bits) - #one)) as #primitive_type
                         }
                     }
                 } else {
                     // Extract arbitrary int (e.g. u7), using one of the extract methods
                     let custom_type = TokenStream2::from_str(format!("arbitrary_int::u{}", number_of_bits).as_str()).unwrap();
-                    let extract = TokenStream2::from_str(format!("extract_u{}", base_data_size).as_str()).unwrap();
-                    if indexed_count.is_some() {
-                        let indexed_stride = indexed_stride.unwrap();
-                        quote! {
-                            #custom_type::#extract(self.raw_value, #lowest_bit + index * #indexed_stride)
-                        }
-                    } else {
-                        quote! {
-                            #custom_type::#extract(self.raw_value, #lowest_bit)
-                        }
+                    let extract = TokenStream2::from_str(format!("extract_u{}", working_data_size).as_str()).unwrap();
+                    quote! {
+                        #custom_type::#extract(#source, #shift)
                     }
                 };
 
                 let converted = match &custom_type {
                     CustomType::No => extracted_bits,
                     CustomType::Yes(convert_type) => {
+                        let converted = quote_spanned! {convert_type.span()=> #convert_type::new_with_raw_value(extracted_bits) };
                         quote! {
                             let extracted_bits = #extracted_bits;
-                            #convert_type::new_with_raw_value(extracted_bits)
+                            #converted
                         }
                     }
                 };
@@ -425,7 +987,9 @@ pub fn bitfield(args: TokenStream, input: TokenStream) -> TokenStream {
                     quote! {
                         #doc_comment
                         #[inline]
-                        pub const fn #field_name(&self, index: usize) -> #getter_type {
+                        pub const fn #field_name(&self, index: #index_argument_type) -> #getter_type {
+                            #index_to_usize
+                            #gather
                             #converted
                         }
                     }
@@ -434,6 +998,7 @@ pub fn bitfield(args: TokenStream, input: TokenStream) -> TokenStream {
                         #doc_comment
                         #[inline]
                         pub const fn #field_name(&self) -> #getter_type {
+                            #gather
                             #converted
                         }
                     }
@@ -442,71 +1007,210 @@ pub fn bitfield(args: TokenStream, input: TokenStream) -> TokenStream {
                 quote! {}
             };
 
-        let setter = if provide_setter {
-            let argument_converted =
-                match custom_type {
-                    CustomType::No => {
-                        if use_regular_int {
-                            quote! { field_value }
-                        } else {
-                            quote! { field_value.value() }
-                        }
+        let setter_name = format_ident!("with_{}", field_name);
+        let argument_converted =
+            match &custom_type {
+                CustomType::No => {
+                    if is_arbitrary_signed {
+                        quote! { ((field_value as #unsigned_primitive_type as #working_data_type) & ((#one << #number_of_bits) - #one)) }
+                    } else if signed_size.is_some() {
+                        quote! { (field_value as #unsigned_primitive_type) }
+                    } else if use_regular_int {
+                        quote! { field_value }
+                    } else {
+                        quote! { field_value.value() }
                     }
-                    CustomType::Yes(_) => {
-                        if use_regular_int {
-                            quote! { field_value.raw_value() }
-                        } else {
-                            quote! { field_value.raw_value().value() }
-                        }
+                }
+                // Spanned to the type, so that a type with a raw value of a different size is reported there
+                CustomType::Yes(convert_type) => {
+                    if use_regular_int {
+                        quote_spanned! {convert_type.span()=> field_value.raw_value() }
+                    } else {
+                        quote_spanned! {convert_type.span()=> field_value.raw_value().value() }
                     }
-                };
+                }
+            };
 
-            let new_raw_value = if let Some(_indexed_count) = indexed_count {
-                let indexed_stride = indexed_stride.unwrap();
-                if field_type_size_from_data_type == Some(1) {
+        // Returns the given raw value with field_value written into the bits of this field
+        let new_raw_value_from = |current_raw_value: TokenStream2| -> TokenStream2 {
+            if is_gathered {
+                // Scatter the value over the pieces, starting with the most significant bits
+                let mut offset = number_of_bits;
+                if base_array.is_some() {
+                    let inserted_pieces: Vec<TokenStream2> = pieces.iter().map(|(piece_lowest_bit, piece_number_of_bits)| {
+                        offset -= piece_number_of_bits;
+                        quote! { let raw_value = #struct_name::__bitbybit_insert_raw_bits(raw_value, #piece_lowest_bit, #piece_number_of_bits, field_bits >> #offset); }
+                    }).collect();
                     quote! {
                         {
-                            let effective_index = #lowest_bit + index * #indexed_stride;
-                            if #argument_converted { self.raw_value | (#one << effective_index) } else { self.raw_value & !(#one << effective_index) }
+                            let field_bits = #argument_converted as #working_data_type;
+                            let raw_value = #current_raw_value;
+                            #( #inserted_pieces )*
+                            raw_value
                         }
                     }
                 } else {
+                    let mut clear_mask: Vec<TokenStream2> = Vec::new();
+                    let scattered_pieces: Vec<TokenStream2> = pieces.iter().map(|(piece_lowest_bit, piece_number_of_bits)| {
+                        offset -= piece_number_of_bits;
+                        clear_mask.push(quote! { (((#one << #piece_number_of_bits) - #one) << #piece_lowest_bit) });
+                        quote! { (((field_bits >> #offset) & ((#one << #piece_number_of_bits) - #one)) << #piece_lowest_bit) }
+                    }).collect();
                     quote! {
                         {
-                            let effective_index = #lowest_bit + index * #indexed_stride;
-                            (self.raw_value & !(((#one << #number_of_bits) - #one) << effective_index)) | ((#argument_converted as #base_data_type) << effective_index)
+                            let field_bits = #argument_converted as #working_data_type;
+                            (#current_raw_value & !(#( #clear_mask )|*)) | #( #scattered_pieces )|*
                         }
                     }
                 }
-            } else {
+            } else if let Some(_indexed_count) = indexed_count {
                 if field_type_size_from_data_type == Some(1) {
                     quote! {
-                        if #argument_converted { self.raw_value | (#one << #lowest_bit) } else { self.raw_value & !(#one << #lowest_bit) }
+                        {
+                            let effective_index = #effective_index;
+                            if #argument_converted { #current_raw_value | (#one << effective_index) } else { #current_raw_value & !(#one << effective_index) }
+                        }
                     }
                 } else {
-                    if number_of_bits == base_data_size {
-                        // If the field is the whole size of the bitfield, we can't apply a mask
-                        // as that would overflow. However, we don't need to
-                        assert_eq!(lowest_bit, 0);
-                        quote! { #argument_converted as #base_data_type }
-                    } else {
-                        quote! { (self.raw_value & !(((#one << #number_of_bits) - #one) << #lowest_bit)) | ((#argument_converted as #base_data_type) << #lowest_bit) }
+                    quote! {
+                        {
+                            let effective_index = #effective_index;
+                            (#current_raw_value & !(((#one << #number_of_bits) - #one) << effective_index)) | ((#argument_converted as #storage_data_type) << effective_index)
+                        }
                     }
                 }
-            };
+            } else if field_type_size_from_data_type == Some(1) {
+                quote! {
+                    if #argument_converted { #current_raw_value | (#one << #lowest_bit) } else { #current_raw_value & !(#one << #lowest_bit) }
+                }
+            } else if number_of_bits == base_data_size {
+                // If the field is the whole size of the bitfield, we can't apply a mask
+                // as that would overflow. However, we don't need to
+                assert_eq!(lowest_bit, 0);
+                quote! { #argument_converted as #storage_data_type }
+            } else {
+                quote! { (#current_raw_value & !(((#one << #number_of_bits) - #one) << #lowest_bit)) | ((#argument_converted as #storage_data_type) << #lowest_bit) }
+            }
+        };
+
+        // The default is written like any other value. Integer literals are turned into arbitrary ints
+        // (e.g. 'default: 5' for an u4) and arrays take either one value for every element or an array
+        let default_value = match &field_default {
+            Some((argument_name, _)) if reserved_value.is_some() => {
+                return Err(syn::Error::new_spanned(argument_name, "Reserved fields can't have a default, as they always have their reserved value"));
+            }
+            Some((argument_name, value)) => {
+                // Literals that don't fit are reported here. Other values are checked by evaluating new() at compile time
+                let element_value = |value: &TokenStream2| -> syn::Result<TokenStream2> {
+                    match syn::parse2::<LitInt>(value.clone()) {
+                        Ok(literal) if matches!(custom_type, CustomType::No) && !use_regular_int && !is_arbitrary_signed => {
+                            if literal.base10_parse::<u128>().map_or(true, |literal_value| literal_value > (1u128 << number_of_bits) - 1) {
+                                return Err(syn::Error::new_spanned(value, format!("Default {} doesn't fit into {} bits", literal, number_of_bits)));
+                            }
+                            Ok(quote! { #setter_type::new(#value) })
+                        }
+                        _ => Ok(value.clone()),
+                    }
+                };
+                let new_raw_value = new_raw_value_from(quote! { raw_value });
+                let code = match indexed_count {
+                    Some(indexed_count) => {
+                        let field_values = match syn::parse2::<proc_macro2::Group>(value.clone()) {
+                            Ok(group) if group.delimiter() == proc_macro2::Delimiter::Bracket => {
+                                // Either a list of elements like [1, 2] or a repeat expression like [1; 2]
+                                let mut elements: Vec<TokenStream2> = vec![TokenStream2::new()];
+                                let mut repeat_count: Option<TokenStream2> = None;
+                                for token in group.stream() {
+                                    match (&token, &mut repeat_count) {
+                                        (_, Some(repeat_count)) => repeat_count.extend([token]),
+                                        (TokenTree::Punct(punct), None) if punct.as_char() == ',' => elements.push(TokenStream2::new()),
+                                        (TokenTree::Punct(punct), None) if punct.as_char() == ';' => repeat_count = Some(TokenStream2::new()),
+                                        _ => elements.last_mut().unwrap().extend([token]),
+                                    }
+                                }
+                                let elements: Vec<TokenStream2> = elements.iter().filter(|element| !element.is_empty()).map(element_value).collect::<syn::Result<_>>()?;
+                                match repeat_count {
+                                    Some(repeat_count) => quote! { [#( #elements )*; #repeat_count] },
+                                    None => quote! { [#( #elements ),*] },
+                                }
+                            }
+                            _ => {
+                                let value = element_value(value)?;
+                                quote! { [#value; #indexed_count] }
+                            }
+                        };
+                        quote! {
+                            {
+                                let field_values: [#setter_type; #indexed_count] = #field_values;
+                                let mut raw_value = raw_value;
+                                let mut index = 0;
+                                while index < #indexed_count {
+                                    let field_value = field_values[index];
+                                    raw_value = #new_raw_value;
+                                    index += 1;
+                                }
+                                raw_value
+                            }
+                        }
+                    }
+                    None => {
+                        let value = element_value(value)?;
+                        quote! {
+                            {
+                                let field_value: #setter_type = #value;
+                                #new_raw_value
+                            }
+                        }
+                    }
+                };
+                Some((argument_name.clone(), code))
+            }
+            None => None,
+        };
+
+        let setter = if provide_setter {
+            let new_raw_value = new_raw_value_from(quote! { self.raw_value });
+            let in_place_setter_name = format_ident!("set_{}", field_name);
+            generated_names.push(setter_name.clone());
+            if in_place_setters {
+                generated_names.push(in_place_setter_name.clone());
+            }
 
-            let setter_name = syn::parse_str::<syn::Ident>(format!("with_{}", field_name.to_string()).as_str()).unwrap_or_else(|_| panic!("bitfield!: Error creating setter name"));
             if let Some(_indexed_count) = indexed_count {
+                let in_place_setter = if in_place_setters {
+                    quote! {
+                        #doc_comment
+                        #[inline]
+                        pub const fn #in_place_setter_name(&mut self, index: #index_argument_type, field_value: #setter_type) {
+                            *self = self.#setter_name(index, field_value);
+                        }
+                    }
+                } else {
+                    quote! {}
+                };
                 quote! {
                     #doc_comment
                     #[inline]
-                    pub const fn #setter_name(&self, index: usize, field_value: #setter_type) -> Self {
+                    pub const fn #setter_name(&self, index: #index_argument_type, field_value: #setter_type) -> Self {
+                        #index_to_usize
                         Self {
                             raw_value: #new_raw_value
                         }
                     }
+                    #in_place_setter
                 }
             } else {
+                let in_place_setter = if in_place_setters {
+                    quote! {
+                        #doc_comment
+                        #[inline]
+                        pub const fn #in_place_setter_name(&mut self, field_value: #setter_type) {
+                            *self = self.#setter_name(field_value);
+                        }
+                    }
+                } else {
+                    quote! {}
+                };
                 quote! {
                     #doc_comment
                     #[inline]
@@ -515,26 +1219,693 @@ pub fn bitfield(args: TokenStream, input: TokenStream) -> TokenStream {
                             raw_value: #new_raw_value
                         }
                     }
+                    #in_place_setter
                 }
             }
         } else {
             quote! {}
         };
 
-        quote! {
-            #getter
-            #setter
-        }
-    }).collect();
-
-    let (default_constructor, default_trait) = if let Some(default_value) = default_value {
-        (
-            quote! {
-                /// Creates a new instance with the default value.
-                ///
+        // Arrays can also be read and written as a whole, e.g. nibble_array() and with_nibble_array() for
+        // a field named nibble. Reserved fields don't get these, as they don't have accessors either
+        let array_accessors = match indexed_count {
+            Some(indexed_count) if reserved_value.is_none() => {
+                let (count_name, array_getter_name, array_setter_name, in_place_array_setter_name) = (format_ident!("{}_count", field_name), format_ident!("{}_array", field_name), format_ident!("with_{}_array", field_name), format_ident!("set_{}_array", field_name));
+                generated_names.push(count_name.clone());
+                let array_getter = if provide_getter {
+                    generated_names.push(array_getter_name.clone());
+                    let indices = (0..indexed_count).map(|index| index_from_usize(quote! { #index }));
+                    quote! {
+                        #doc_comment
+                        #[inline]
+                        pub const fn #array_getter_name(&self) -> [#getter_type; #indexed_count] {
+                            [ #( self.#field_name(#indices) ),* ]
+                        }
+                    }
+                } else {
+                    quote! {}
+                };
+                let array_setter = if provide_setter {
+                    generated_names.push(array_setter_name.clone());
+                    let loop_index = index_from_usize(quote! { index });
+                    let in_place_array_setter = if in_place_setters {
+                        generated_names.push(in_place_array_setter_name.clone());
+                        quote! {
+                            #doc_comment
+                            #[inline]
+                            pub const fn #in_place_array_setter_name(&mut self, field_values: [#setter_type; #indexed_count]) {
+                                *self = self.#array_setter_name(field_values);
+                            }
+                        }
+                    } else {
+                        quote! {}
+                    };
+                    quote! {
+                        #doc_comment
+                        #[inline]
+                        pub const fn #array_setter_name(&self, field_values: [#setter_type; #indexed_count]) -> Self {
+                            let mut result = *self;
+                            let mut index = 0;
+                            while index < #indexed_count {
+                                result = result.#setter_name(#loop_index, field_values[index]);
+                                index += 1;
+                            }
+                            result
+                        }
+                        #in_place_array_setter
+                    }
+                } else {
+                    quote! {}
+                };
+                // With usize indices, try_ accessors detect indices that are out of range. Bounded index
+                // types can't be out of range
+                let try_accessors = if index_type.is_none() {
+                    let try_getter = if provide_getter {
+                        let try_getter_name = format_ident!("try_{}", field_name);
+                        generated_names.push(try_getter_name.clone());
+                        quote! {
+                            /// Returns None if the index is out of range
+                            #[inline]
+                            pub const fn #try_getter_name(&self, index: usize) -> Option<#getter_type> {
+                                if index < #indexed_count { Some(self.#field_name(index)) } else { None }
+                            }
+                        }
+                    } else {
+                        quote! {}
+                    };
+                    let try_setter = if provide_setter {
+                        let try_setter_name = format_ident!("try_with_{}", field_name);
+                        generated_names.push(try_setter_name.clone());
+                        quote! {
+                            /// Returns None if the index is out of range
+                            #[inline]
+                            pub const fn #try_setter_name(&self, index: usize, field_value: #setter_type) -> Option<Self> {
+                                if index < #indexed_count { Some(self.#setter_name(index, field_value)) } else { None }
+                            }
+                        }
+                    } else {
+                        quote! {}
+                    };
+                    quote! {
+                        #try_getter
+                        #try_setter
+                    }
+                } else {
+                    quote! {}
+                };
+                quote! {
+                    /// Number of elements of the field
+                    pub const fn #count_name() -> usize { #indexed_count }
+                    #array_getter
+                    #array_setter
+                    #try_accessors
+                }
+            }
+            _ => quote! {},
+        };
+
+        // Fields with side effects on write get a dedicated operation instead of a setter. It returns a
+        // value in which only this field triggers its side effect (see without_side_effects()). The
+        // w1s operation is called trigger_ rather than set_, as set_ changes a value in place
+        let side_effect_operation = match side_effect {
+            Some(SideEffect::W1c) | Some(SideEffect::W0c) | Some(SideEffect::W1s) => {
+                if !matches!(custom_type, CustomType::No) || signed_size.is_some() {
+                    return Err(syn::Error::new_spanned(ty, format!("Field {} has a write side effect, which is only supported for bool and unsigned integers", field_name)));
+                }
+                let sets_bits = !matches!(side_effect, Some(SideEffect::W0c));
+                let field_value = if field_type_size_from_data_type == Some(1) {
+                    quote! { #sets_bits }
+                } else if sets_bits && use_regular_int {
+                    quote! { #ty::MAX }
+                } else if sets_bits {
+                    let max_value = syn::LitInt::new(format!("0x{:X}", (1u128 << number_of_bits) - 1).as_str(), proc_macro2::Span::call_site());
+                    quote! { #ty::new(#max_value) }
+                } else if use_regular_int {
+                    quote! { 0 }
+                } else {
+                    quote! { #ty::new(0) }
+                };
+                let (operation_name, description) = match side_effect {
+                    Some(SideEffect::W1s) => (format_ident!("trigger_{}", field_name), "sets the field by writing ones"),
+                    Some(SideEffect::W1c) => (format_ident!("clear_{}", field_name), "clears the field by writing ones"),
+                    _ => (format_ident!("clear_{}", field_name), "clears the field by writing zeros"),
+                };
+                generated_names.push(operation_name.clone());
+                let operation_doc = format!("Returns a value that {} when written. All other fields with write side effects are left unchanged.", description);
+                let new_raw_value = new_raw_value_from(quote! { self.without_side_effects().raw_value });
+                let index_argument = if indexed_count.is_some() { quote! { index: #index_argument_type } } else { quote! {} };
+                quote! {
+                    #[doc = #operation_doc]
+                    #[inline]
+                    #[must_use]
+                    pub const fn #operation_name(&self, #index_argument) -> Self {
+                        #index_to_usize
+                        let field_value: #setter_type = #field_value;
+                        Self {
+                            raw_value: #new_raw_value
+                        }
+                    }
+                }
+            }
+            Some(SideEffect::Rc) | None => quote! {},
+        };
+
+        // Value of each bit of write side effect fields that doesn't trigger the side effect
+        let side_effect_bits: Vec<(usize, bool)> = match side_effect {
+            Some(SideEffect::W1c) | Some(SideEffect::W1s) => placements.iter().flatten().flat_map(|range| range.clone().map(|bit| (bit, false))).collect(),
+            Some(SideEffect::W0c) => placements.iter().flatten().flat_map(|range| range.clone().map(|bit| (bit, true))).collect(),
+            Some(SideEffect::Rc) | None => Vec::new(),
+        };
+
+        // Write-only fields can't be read, so they are skipped by Debug
+        let debug_field = if provide_getter {
+            let field_name_string = field_name.unraw().to_string();
+            match indexed_count {
+                Some(indexed_count) => {
+                    let indices = (0..indexed_count).map(|index| index_from_usize(quote! { #index }));
+                    quote! { .field(#field_name_string, &[ #( self.#field_name(#indices) ),* ]) }
+                }
+                None => quote! { .field(#field_name_string, &self.#field_name()) },
+            }
+        } else {
+            quote! {}
+        };
+        let defmt_field = if provide_getter && defmt {
+            let value = match indexed_count {
+                Some(indexed_count) => {
+                    let indices = (0..indexed_count).map(|index| index_from_usize(quote! { #index }));
+                    quote! { [ #( self.#field_name(#indices) ),* ] }
+                }
+                None => quote! { self.#field_name() },
+            };
+            Some((field_name.unraw().to_string(), value))
+        } else {
+            None
+        };
+
+        // Masks and shifts for interop with code that works on raw values (e.g. C drivers). Reserved
+        // fields don't get any, as they don't have accessors either
+        let constants = if reserved_value.is_some() {
+            quote! {}
+        } else {
+            let constant_prefix = field_name.unraw().to_string().to_uppercase();
+            let constant_name = |suffix: &str| constant_ident(format!("{}_{}", constant_prefix, suffix).as_str(), field_name.span());
+            let (mask_name, shift_name, width_name, stride_name) = (constant_name("MASK")?, constant_name("SHIFT")?, constant_name("WIDTH")?, constant_name("STRIDE")?);
+            generated_names.push(width_name.clone());
+            let width = quote! {
+                /// Number of bits of the field
+                pub const #width_name: usize = #number_of_bits;
+            };
+            match (indexed_count, &split_pieces) {
+                (Some(_), _) => {
+                    let mask_function_name = format_ident!("{}_mask", field_name);
+                    let shift_function_name = format_ident!("{}_shift", field_name);
+                    generated_names.extend([stride_name.clone(), mask_function_name.clone(), shift_function_name.clone()]);
+                    let indexed_stride = indexed_stride.unwrap();
+                    let mask = match &base_array {
+                        Some((_, element_size)) => {
+                            let element_count = base_data_size / element_size;
+                            quote! { Self::__bitbybit_insert_raw_bits([0; #element_count], #effective_index, #number_of_bits, u128::MAX) }
+                        }
+                        None => to_raw_value_data_type(quote! { ((#one << #number_of_bits) - #one) << #effective_index }),
+                    };
+                    quote! {
+                        #width
+                        /// Number of bits between the lowest bits of two consecutive elements
+                        pub const #stride_name: usize = #indexed_stride;
+
+                        /// Returns the bits of the element at the given index within the raw value
+                        #[inline]
+                        pub const fn #mask_function_name(index: usize) -> #raw_value_data_type {
+                            #mask
+                        }
+
+                        /// Returns the lowest bit of the element at the given index within the raw value
+                        #[inline]
+                        pub const fn #shift_function_name(index: usize) -> usize {
+                            #effective_index
+                        }
+                    }
+                }
+                (None, Some(_)) => {
+                    // The pieces of split fields can't be described by a single shift
+                    let (mask, _) = to_mask_and_value(&placements[0].iter().flat_map(|range| range.clone().map(|bit| (bit, true))).collect::<Vec<_>>());
+                    let mask = to_raw_value_data_type(mask);
+                    generated_names.push(mask_name.clone());
+                    quote! {
+                        #width
+                        /// Bits of the field within the raw value
+                        pub const #mask_name: #raw_value_data_type = #mask;
+                    }
+                }
+                (None, None) => {
+                    let (mask, _) = to_mask_and_value(&(lowest_bit..lowest_bit + number_of_bits).map(|bit| (bit, true)).collect::<Vec<_>>());
+                    let mask = to_raw_value_data_type(mask);
+                    generated_names.extend([mask_name.clone(), shift_name.clone()]);
+                    quote! {
+                        #width
+                        /// Bits of the field within the raw value
+                        pub const #mask_name: #raw_value_data_type = #mask;
+                        /// Lowest bit of the field within the raw value
+                        pub const #shift_name: usize = #lowest_bit;
+                    }
+                }
+            }
+        };
+
+        // Serialization of readable fields and deserialization of all fields except reserved ones.
+        // Deserialization writes the bits directly, so that read-only fields can be restored as well
+        let field_name_string = field_name.unraw().to_string();
+        let serialize_field = if provide_getter && serde_mode.is_some() {
+            let value = |index: TokenStream2| -> TokenStream2 {
+                if is_option_type {
+                    quote! { RawOr(self.#field_name(#index)) }
+                } else {
+                    quote! { self.#field_name(#index) }
+                }
+            };
+            match indexed_count {
+                Some(indexed_count) => {
+                    let values = (0..indexed_count).map(|index| value(index_from_usize(quote! { #index })));
+                    quote! { state.serialize_field(#field_name_string, &ArrayOf([ #( #values ),* ]))?; }
+                }
+                None => {
+                    let value = value(quote! {});
+                    quote! { state.serialize_field(#field_name_string, &#value)?; }
+                }
+            }
+        } else {
+            quote! {}
+        };
+        let deserialize_field = if reserved_value.is_none() && serde_mode.is_some() {
+            let new_raw_value = new_raw_value_from(quote! { raw_value });
+            let set_value = if is_option_type {
+                // Unknown enum values are given as raw number and written as is
+                let bounded_type = if use_regular_int {
+                    quote! { #primitive_type }
+                } else {
+                    TokenStream2::from_str(format!("arbitrary_int::u{}", number_of_bits).as_str()).unwrap()
+                };
+                let raw_to_bounded = if use_regular_int {
+                    quote! { raw }
+                } else {
+                    let max_value = syn::LitInt::new(format!("0x{:X}", (1u128 << number_of_bits) - 1).as_str(), proc_macro2::Span::call_site());
+                    quote! {
+                        if raw > #max_value {
+                            return Err(serde::de::Error::custom("raw value is out of range"));
+                        } else {
+                            #bounded_type::new(raw)
+                        }
+                    }
+                };
+                quote! {
+                    match value.0 {
+                        Ok(field_value) => raw_value = #new_raw_value,
+                        Err(raw) => {
+                            let field_value = RawBits(#raw_to_bounded);
+                            raw_value = #new_raw_value;
+                        }
+                    }
+                }
+            } else {
+                quote! {
+                    let field_value = value;
+                    raw_value = #new_raw_value;
+                }
+            };
+            let value_type = if is_option_type {
+                quote! { RawOr<#setter_type, #primitive_type> }
+            } else {
+                quote! { #setter_type }
+            };
+            Some(match indexed_count {
+                Some(indexed_count) => quote! {
+                    let values = map.next_value::<ArrayOf<#value_type, #indexed_count>>()?;
+                    let mut index = 0;
+                    while index < #indexed_count {
+                        let value = values.0[index];
+                        #set_value
+                        index += 1;
+                    }
+                },
+                None => quote! {
+                    let value = map.next_value::<#value_type>()?;
+                    #set_value
+                },
+            })
+        } else {
+            None
+        };
+
+        // Custom types are created from a raw value of the size of the field. As their size isn't known to the
+        // macro, it is checked at compile time
+        let const_assertions = match &custom_type {
+            CustomType::Yes(convert_type) => {
+                let message = format!("{} doesn't have a raw value of {} bits, which is the size of field {}", convert_type.to_token_stream(), number_of_bits, field_name.unraw());
+                quote_spanned! {convert_type.span()=>
+                    const _: () = {
+                        const fn raw_value_bits<T: arbitrary_int::Number, R>(_: fn(T) -> R) -> usize {
+                            T::BITS
+                        }
+                        assert!(raw_value_bits(#convert_type::new_with_raw_value) == #number_of_bits, #message);
+                    };
+                }
+            }
+            CustomType::No => quote! {},
+        };
+
+        let flag_bit = if field_type_size_from_data_type == Some(1) && indexed_count.is_none() && reserved_value.is_none() { Some(lowest_bit) } else { None };
+        if flags && flag_bit.is_some() {
+            generated_names.push(format_ident!("{}", field_name.unraw().to_string().to_uppercase()));
+        }
+
+        Ok(FieldOutput {
+            name: field_name.to_string(),
+            ident: field_name.clone(),
+            serialize_field,
+            deserialize_field: deserialize_field.map(|code| (field_name_string, code)),
+            is_array: indexed_count.is_some(),
+            is_option_type,
+            occupied_bits: placements.into_iter().flatten().collect(),
+            overlaps,
+            accessors: quote! {
+                #constants
+                #getter
+                #setter
+                #array_accessors
+                #side_effect_operation
+            },
+            side_effect_bits,
+            is_read_clear: matches!(side_effect, Some(SideEffect::Rc)),
+            flag_bit,
+            debug_field,
+            defmt_field,
+            readable: provide_getter,
+            reserved_bits,
+            writable: if provide_setter { Some((setter_name, setter_type, indexed_count)) } else { None },
+            default_value,
+            generated_names,
+            const_assertions,
+        })
+    }).filter_map(|field_output| field_output.map_err(|error| errors.push(error)).ok()).collect();
+    errors.check()?;
+    // Functions and constants must not be generated by several fields, e.g. flag_array() by both
+    // flag: [bool; 8] and flag_array: u8
+    for (i, field) in field_outputs.iter().enumerate() {
+        for other in field_outputs.iter().skip(i + 1) {
+            if let Some(name) = other
+                .generated_names
+                .iter()
+                .find(|name| field.generated_names.contains(name))
+            {
+                errors.add(
+                    other.ident.span(),
+                    format!(
+                        "Fields {} and {} both generate {}. Rename one of them",
+                        field.name, other.name, name
+                    ),
+                );
+            }
+        }
+    }
+    // Fields also share the namespace with the functions that are generated for the whole bitfield.
+    // Private helpers use the __bitbybit prefix
+    let mut struct_generated_names = vec!["raw_value", "new_with_raw_value"];
+    if default_value.is_some()
+        || field_outputs
+            .iter()
+            .any(|output| output.default_value.is_some())
+    {
+        struct_generated_names.push("new");
+    }
+    if builder {
+        struct_generated_names.push("builder");
+    }
+    if field_outputs
+        .iter()
+        .any(|output| !output.reserved_bits.is_empty())
+    {
+        struct_generated_names.push("try_new_with_raw_value");
+    }
+    if field_outputs
+        .iter()
+        .any(|output| !output.side_effect_bits.is_empty())
+    {
+        struct_generated_names.push("without_side_effects");
+    }
+    if !is_arbitrary_base {
+        struct_generated_names.extend([
+            "to_le_bytes",
+            "to_be_bytes",
+            "from_le_bytes",
+            "from_be_bytes",
+            "from_le_slice",
+            "from_be_slice",
+            "from_bytes",
+        ]);
+    }
+    for field in &field_outputs {
+        if let Some(name) = field.generated_names.iter().find(|name| {
+            let name = name.to_string();
+            struct_generated_names.contains(&name.as_str())
+                || name.to_lowercase().starts_with("__bitbybit")
+        }) {
+            errors.add(
+                field.ident.span(),
+                format!(
+                    "Field {} generates {}, which is already generated for the bitfield. Rename the field",
+                    field.name, name
+                ),
+            );
+        }
+    }
+    // Validate the layout: Fields must not overlap, unless explicitly allowed via 'overlaps'. That
+    // every field is within the bitfield was already checked per field
+    for (i, field) in field_outputs.iter().enumerate() {
+        for other_name in &field.overlaps {
+            if !field_outputs
+                .iter()
+                .any(|other| *other_name == other.name && other.name != field.name)
+            {
+                errors.add(
+                    other_name.span(),
+                    format!(
+                        "Field {} overlaps with {}, but there is no other field with that name",
+                        field.name, other_name
+                    ),
+                );
+            }
+        }
+        for other in field_outputs.iter().skip(i + 1) {
+            let overlapping_bit = field.occupied_bits.iter().find_map(|range| {
+                other.occupied_bits.iter().find_map(|other_range| {
+                    let start = range.start.max(other_range.start);
+                    if start < range.end.min(other_range.end) {
+                        Some(start)
+                    } else {
+                        None
+                    }
+                })
+            });
+            if let Some(overlapping_bit) = overlapping_bit {
+                // Report the bit the way it was written
+                let overlapping_bit = if is_msb0 {
+                    base_data_size - 1 - overlapping_bit
+                } else {
+                    overlapping_bit
+                };
+                if !field.overlaps.iter().any(|name| *name == other.name)
+                    && !other.overlaps.iter().any(|name| *name == field.name)
+                {
+                    errors.add(other.ident.span(), format!("Fields {} and {} overlap (e.g. at bit {}). If this is intended, add 'overlaps: {}' to {}", field.name, other.name, overlapping_bit, field.name, other.name));
+                }
+            }
+        }
+    }
+    errors.check()?;
+
+    let accessors: Vec<&TokenStream2> = field_outputs
+        .iter()
+        .map(|output| &output.accessors)
+        .collect();
+    let const_assertions: Vec<&TokenStream2> = field_outputs
+        .iter()
+        .map(|output| &output.const_assertions)
+        .collect();
+    let debug_fields: Vec<&TokenStream2> = field_outputs
+        .iter()
+        .map(|output| &output.debug_field)
+        .collect();
+
+    let reserved_bits: Vec<(usize, bool)> = field_outputs
+        .iter()
+        .flat_map(|output| output.reserved_bits.iter().copied())
+        .collect();
+    let (reserved_mask, reserved_value) = to_mask_and_value(&reserved_bits);
+    let has_reserved_bits = !reserved_bits.is_empty();
+    // Enforces the reserved values on a raw value that is known at compile time
+    let with_reserved_bits = |raw_value: TokenStream2| -> TokenStream2 {
+        if has_reserved_bits {
+            quote! { #struct_name::__bitbybit_with_reserved_bits(#raw_value) }
+        } else {
+            raw_value
+        }
+    };
+    let reserved_functions = if has_reserved_bits {
+        let (raw_value_to_storage, storage_to_raw_value) = if is_arbitrary_base {
+            (
+                quote! { value.value() },
+                quote! { arbitrary_int::#base_data_type::new(wrong_bits) },
+            )
+        } else {
+            (quote! { value }, quote! { wrong_bits })
+        };
+        let with_reserved_bits_body = apply_mask_and_value(
+            quote! { Self::__BITBYBIT_RESERVED_BITS_MASK },
+            quote! { Self::__BITBYBIT_RESERVED_BITS_VALUE },
+        );
+        let try_new_body = match &base_array {
+            Some((_, element_size)) => {
+                let element_count = base_data_size / element_size;
+                quote! {
+                        let mut wrong_bits = [0; #element_count];
+                        let mut is_valid = true;
+                        let mut i = 0;
+                        while i < #element_count {
+                            wrong_bits[i] = (value[i] ^ Self::__BITBYBIT_RESERVED_BITS_VALUE[i]) & Self::__BITBYBIT_RESERVED_BITS_MASK[i];
+                            if wrong_bits[i] != 0 {
+                                is_valid = false;
+                            }
+                            i += 1;
+                        }
+                        if is_valid { Ok(Self { raw_value: value }) } else { Err(wrong_bits) }
+                }
+            }
+            None => quote! {
+                let raw_value = #raw_value_to_storage;
+                let wrong_bits = (raw_value ^ Self::__BITBYBIT_RESERVED_BITS_VALUE) & Self::__BITBYBIT_RESERVED_BITS_MASK;
+                if wrong_bits == 0 { Ok(Self { raw_value }) } else { Err(#storage_to_raw_value) }
+            },
+        };
+        quote! {
+            const __BITBYBIT_RESERVED_BITS_MASK: #storage_data_type = #reserved_mask;
+            const __BITBYBIT_RESERVED_BITS_VALUE: #storage_data_type = #reserved_value;
+
+            #[inline]
+            #[allow(unused_mut)]
+            const fn __bitbybit_with_reserved_bits(mut raw_value: #storage_data_type) -> #storage_data_type {
+                #with_reserved_bits_body
+            }
+
+            /// Creates a new instance of this bitfield with the given raw value if all reserved bits
+            /// have their required value. Otherwise, returns Err() with all reserved bits that don't.
+            #[inline]
+            pub const fn try_new_with_raw_value(value: #base_data_type) -> Result<Self, #base_data_type> {
+                #try_new_body
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    // Fields with write side effects (e.g. write 1 to clear) must be neutralized before a value that was
+    // read is written back, as that would otherwise trigger the side effects (e.g. clear pending interrupts)
+    let side_effect_bits: Vec<(usize, bool)> = field_outputs
+        .iter()
+        .flat_map(|output| output.side_effect_bits.iter().copied())
+        .collect();
+    let has_side_effect_bits = !side_effect_bits.is_empty();
+    let side_effect_functions = if has_side_effect_bits {
+        let (side_effect_mask, side_effect_neutral_value) = to_mask_and_value(&side_effect_bits);
+        let body = apply_mask_and_value(
+            quote! { Self::__BITBYBIT_SIDE_EFFECT_BITS_MASK },
+            quote! { Self::__BITBYBIT_SIDE_EFFECT_BITS_NEUTRAL_VALUE },
+        );
+        quote! {
+            const __BITBYBIT_SIDE_EFFECT_BITS_MASK: #storage_data_type = #side_effect_mask;
+            const __BITBYBIT_SIDE_EFFECT_BITS_NEUTRAL_VALUE: #storage_data_type = #side_effect_neutral_value;
+
+            /// Returns a copy in which all fields with write side effects (w1c, w0c, w1s) are set so
+            /// that writing the value doesn't trigger them. Use this before writing back a value
+            /// that was read.
+            #[inline]
+            #[must_use]
+            #[allow(unused_mut)]
+            pub const fn without_side_effects(&self) -> Self {
+                let mut raw_value = self.raw_value;
+                Self {
+                    raw_value: { #body },
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    // The default is either given for the whole bitfield or per field. Per field defaults are
+    // applied one after the other, starting from zero
+    let field_defaults: Vec<&(syn::Ident, TokenStream2)> = field_outputs
+        .iter()
+        .filter_map(|output| output.default_value.as_ref())
+        .collect();
+    let default_value = match default_value {
+        Some(default_value) => {
+            for (argument_name, _) in &field_defaults {
+                errors.add(argument_name.span(), "Field defaults can't be combined with a default for the whole bitfield. Remove one of them");
+            }
+            // A literal that doesn't fit into an arbitrary int base data type is reported here. Other
+            // values are checked by evaluating new() at compile time
+            if is_arbitrary_base {
+                if let Ok(literal) = syn::parse2::<LitInt>(default_value.clone()) {
+                    if literal
+                        .base10_parse::<u128>()
+                        .map_or(true, |value| value > (1u128 << base_data_size) - 1)
+                    {
+                        errors.add_spanned(
+                            &default_value,
+                            format!(
+                                "Default {} doesn't fit into {} bits",
+                                literal, base_data_size
+                            ),
+                        );
+                    }
+                }
+            }
+            errors.check()?;
+            let default_value = if is_arbitrary_base {
+                quote! { arbitrary_int::#base_data_type::new(#default_value).value() }
+            } else {
+                default_value
+            };
+            // Reserved bits always have their required value, regardless of the default
+            Some(with_reserved_bits(default_value))
+        }
+        None if !field_defaults.is_empty() => {
+            let zero =
+                with_reserved_bits(to_raw_value_literal(&vec![0; base_data_size / chunk_size]));
+            let field_defaults = field_defaults
+                .iter()
+                .map(|(_, field_default)| field_default);
+            Some(quote! {
+                {
+                    let raw_value = #zero;
+                    #( let raw_value = #field_defaults; )*
+                    raw_value
+                }
+            })
+        }
+        None => None,
+    };
+    let (default_constructor, default_trait) = if let Some(default_value) = default_value {
+        (
+            quote! {
+                /// Creates a new instance with the default value.
+                ///
                 /// For example, the following definition would create an instance with a
                 /// raw_value of 0x123:
                 /// #[bitfield(u32, default: 0x123)]
+                ///
+                /// Defaults can also be given per field, e.g. #[bits(4..=7, rw, default: 5)]
                 #[inline]
                 pub const fn new() -> #struct_name { #struct_name { raw_value: #default_value } }
             },
@@ -544,36 +1915,967 @@ pub fn bitfield(args: TokenStream, input: TokenStream) -> TokenStream {
                         Self::new()
                     }
                 }
+
+                // Evaluating new() at compile time turns a default that doesn't fit into a build error
+                // instead of a panic when new() is called
+                const _: #struct_name = #struct_name::new();
+            },
+        )
+    } else {
+        (quote! {}, quote! {})
+    };
+
+    // Bitwise operators and a constant for each single bit field, so that bitfields can be combined like
+    // flags (e.g. Irq::TIMER | Irq::UART)
+    let (flag_constants, flag_traits) = if flags {
+        let flag_constants: Vec<TokenStream2> = field_outputs
+            .iter()
+            .filter_map(|output| {
+                output.flag_bit.and_then(|bit| {
+                    let constant_name = constant_ident(
+                        output.ident.unraw().to_string().to_uppercase().as_str(),
+                        output.ident.span(),
+                    )
+                    .map_err(|error| errors.push(error))
+                    .ok()?;
+                    let (mask, _) = to_mask_and_value(&[(bit, true)]);
+                    let raw_value = with_reserved_bits(mask);
+                    let doc = format!("Value in which only {} is set", output.name);
+                    Some(quote! {
+                        #[doc = #doc]
+                        pub const #constant_name: Self = Self { raw_value: #raw_value };
+                    })
+                })
+            })
+            .collect();
+        errors.check()?;
+        // Applies a binary operator to the raw values (element wise for arrays). Reserved bits are
+        // restored afterwards, as e.g. xor would clear them
+        let binary_operation =
+            |operator: &TokenStream2, assign_operator: &TokenStream2| -> TokenStream2 {
+                let raw_value = match &base_array {
+                    Some((_, element_size)) => {
+                        let element_count = base_data_size / element_size;
+                        quote! {
+                            {
+                                let mut raw_value = self.raw_value;
+                                let mut i = 0;
+                                while i < #element_count {
+                                    raw_value[i] #assign_operator rhs.raw_value[i];
+                                    i += 1;
+                                }
+                                raw_value
+                            }
+                        }
+                    }
+                    None => quote! { (self.raw_value #operator rhs.raw_value) },
+                };
+                let raw_value = with_reserved_bits(raw_value);
+                quote! { Self { raw_value: #raw_value } }
+            };
+        // Not must neither set bits beyond an arbitrary int base data type nor change reserved bits
+        let inverted = match &base_array {
+            Some((_, element_size)) => {
+                let element_count = base_data_size / element_size;
+                quote! {
+                    {
+                        let mut raw_value = self.raw_value;
+                        let mut i = 0;
+                        while i < #element_count {
+                            raw_value[i] = !raw_value[i];
+                            i += 1;
+                        }
+                        raw_value
+                    }
+                }
+            }
+            None if is_arbitrary_base => {
+                let (mask, _) = to_mask_and_value(
+                    &(0..base_data_size)
+                        .map(|bit| (bit, true))
+                        .collect::<Vec<_>>(),
+                );
+                quote! { (!self.raw_value & #mask) }
+            }
+            None => quote! { !self.raw_value },
+        };
+        let inverted = with_reserved_bits(inverted);
+        let mut traits: Vec<TokenStream2> = Vec::new();
+        for (
+            trait_name,
+            function_name,
+            assign_trait_name,
+            assign_function_name,
+            operator,
+            assign_operator,
+        ) in [
+            (
+                quote! { BitOr },
+                quote! { bitor },
+                quote! { BitOrAssign },
+                quote! { bitor_assign },
+                quote! { | },
+                quote! { |= },
+            ),
+            (
+                quote! { BitAnd },
+                quote! { bitand },
+                quote! { BitAndAssign },
+                quote! { bitand_assign },
+                quote! { & },
+                quote! { &= },
+            ),
+            (
+                quote! { BitXor },
+                quote! { bitxor },
+                quote! { BitXorAssign },
+                quote! { bitxor_assign },
+                quote! { ^ },
+                quote! { ^= },
+            ),
+        ] {
+            let body = binary_operation(&operator, &assign_operator);
+            traits.push(quote! {
+                impl core::ops::#trait_name for #struct_name {
+                    type Output = Self;
+
+                    #[inline]
+                    fn #function_name(self, rhs: Self) -> Self {
+                        #body
+                    }
+                }
+
+                impl core::ops::#assign_trait_name for #struct_name {
+                    #[inline]
+                    fn #assign_function_name(&mut self, rhs: Self) {
+                        *self = *self #operator rhs;
+                    }
+                }
+            });
+        }
+        (
+            quote! { #( #flag_constants )* },
+            quote! {
+                #( #traits )*
+
+                impl core::ops::Not for #struct_name {
+                    type Output = Self;
+
+                    #[inline]
+                    fn not(self) -> Self {
+                        Self { raw_value: #inverted }
+                    }
+                }
             },
         )
     } else {
         (quote! {}, quote! {})
     };
 
+    let debug_trait = if debug {
+        let struct_name_string = struct_name.to_string();
+        quote! {
+            impl core::fmt::Debug for #struct_name {
+                fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                    f.debug_struct(#struct_name_string)
+                        #( #debug_fields )*
+                        .finish()
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    // defmt formats with a format string which is known at compile time. Each field value is
+    // formatted through its own defmt::Format implementation
+    let defmt_trait = if defmt {
+        let defmt_fields: Vec<&(String, TokenStream2)> = field_outputs
+            .iter()
+            .filter_map(|output| output.defmt_field.as_ref())
+            .collect();
+        let format_string = if defmt_fields.is_empty() {
+            struct_name.to_string()
+        } else {
+            let fields: Vec<String> = defmt_fields
+                .iter()
+                .map(|(name, _)| format!("{}: {{}}", name))
+                .collect();
+            format!("{} {{{{ {} }}}}", struct_name, fields.join(", "))
+        };
+        let values = defmt_fields.iter().map(|(_, value)| value);
+        quote! {
+            impl defmt::Format for #struct_name {
+                fn format(&self, f: defmt::Formatter) {
+                    defmt::write!(f, #format_string #( , #values )*)
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    let builder_initial_value = if default_constructor.is_empty() {
+        match &base_array {
+            Some((_, element_size)) => {
+                let element_count = base_data_size / element_size;
+                let raw_value = with_reserved_bits(quote! { [0; #element_count] });
+                quote! { #struct_name { raw_value: #raw_value } }
+            }
+            None => {
+                let raw_value = with_reserved_bits(quote! { 0 });
+                quote! { #struct_name { raw_value: #raw_value } }
+            }
+        }
+    } else {
+        quote! { #struct_name::new() }
+    };
+
+    // The builder has a const bool per writable field, which turns true once the field is set. Each
+    // setter is only implemented while its field is false and build() only once all of them are true.
+    // Overlapping fields are alternate views of the same bits, so they are optional and don't take
+    // part in the typestate
+    let (builder, builder_constructor) = if builder {
+        let builder_name = format_ident!("{}Builder", struct_name);
+        let is_overlapping = |output: &FieldOutput| {
+            !output.overlaps.is_empty()
+                || field_outputs
+                    .iter()
+                    .any(|other| other.overlaps.contains(&output.ident))
+        };
+        let (overlapping_fields, writable_fields): (Vec<_>, Vec<_>) = field_outputs
+            .iter()
+            .filter_map(|output| output.writable.as_ref().map(|writable| (output, writable)))
+            .partition(|(output, _)| is_overlapping(output));
+        let builder_generics: Vec<syn::Ident> = (0..writable_fields.len())
+            .map(|i| format_ident!("F{}", i))
+            .collect();
+        let argument_type_and_value =
+            |(setter_name, setter_type, indexed_count): &(syn::Ident, Type, Option<usize>)| {
+                match indexed_count {
+                    Some(indexed_count) => (quote! { [#setter_type; #indexed_count] }, {
+                        let array_setter_name = format_ident!("{}_array", setter_name);
+                        quote! { self.value.#array_setter_name(field_value) }
+                    }),
+                    None => (
+                        quote! { #setter_type },
+                        quote! { self.value.#setter_name(field_value) },
+                    ),
+                }
+            };
+        let builder_setters: Vec<TokenStream2> = writable_fields.iter().enumerate().map(|(i, (_, writable))| {
+            let setter_name = &writable.0;
+            let other_generics: Vec<&syn::Ident> = builder_generics.iter().enumerate().filter(|(j, _)| *j != i).map(|(_, generic)| generic).collect();
+            let state = |is_set: bool| -> Vec<TokenStream2> {
+                builder_generics.iter().enumerate().map(|(j, generic)| if i == j { quote! { #is_set } } else { quote! { #generic } }).collect()
+            };
+            let (state_before, state_after) = (state(false), state(true));
+            let (argument_type, set_value) = argument_type_and_value(writable);
+            quote! {
+                impl<#( const #other_generics: bool ),*> #builder_name<#( #state_before ),*> {
+                    #[inline]
+                    pub const fn #setter_name(self, field_value: #argument_type) -> #builder_name<#( #state_after ),*> {
+                        #builder_name { value: { #set_value } }
+                    }
+                }
+            }
+        }).collect();
+        let overlapping_setters = overlapping_fields.iter().map(|(_, writable)| {
+            let setter_name = &writable.0;
+            let (argument_type, set_value) = argument_type_and_value(writable);
+            quote! {
+                #[inline]
+                pub const fn #setter_name(self, field_value: #argument_type) -> Self {
+                    #builder_name { value: { #set_value } }
+                }
+            }
+        });
+        let all_set = writable_fields.iter().map(|_| quote! { true });
+        let all_unset = writable_fields.iter().map(|_| quote! { false });
+        let builder_doc = format!(
+            "Builder for {}, which only allows calling build() once every writable field was set",
+            struct_name
+        );
+        // Fields with write side effects start out neutral, so that writing the built value doesn't
+        // trigger them (e.g. w0c fields would be cleared by zeros)
+        let builder_initial_value = if has_side_effect_bits {
+            quote! { (#builder_initial_value).without_side_effects() }
+        } else {
+            builder_initial_value.clone()
+        };
+        (
+            quote! {
+                #[doc = #builder_doc]
+                #struct_vis struct #builder_name<#( const #builder_generics: bool ),*> {
+                    value: #struct_name,
+                }
+
+                #( #builder_setters )*
+
+                impl<#( const #builder_generics: bool ),*> #builder_name<#( #builder_generics ),*> {
+                    #( #overlapping_setters )*
+                }
+
+                impl #builder_name<#( #all_set ),*> {
+                    #[inline]
+                    pub const fn build(self) -> #struct_name {
+                        self.value
+                    }
+                }
+            },
+            quote! {
+                /// Returns a builder, which requires every writable field to be set exactly once. Fields
+                /// that overlap other fields are optional. Fields without a setter keep the default value
+                /// if one is given, or 0 otherwise. Fields with write side effects are neutral (see
+                /// without_side_effects())
+                #[inline]
+                pub const fn builder() -> #builder_name<#( #all_unset ),*> {
+                    #builder_name { value: #builder_initial_value }
+                }
+            },
+        )
+    } else {
+        (quote! {}, quote! {})
+    };
+
+    // serde support. The implementations and their helper types are placed in an anonymous const, so
+    // that helpers of several bitfields within the same module don't collide
+    let serde_implementations = match &serde_mode {
+        None => quote! {},
+        Some(serde_mode) => {
+            let struct_name_string = struct_name.to_string();
+            // The raw value is also used by fields mode in formats that aren't human readable (like
+            // postcard or bincode), as these can't skip or reorder fields
+            let (raw_serialize, raw_deserialize) = {
+                let serialize = if base_array.is_some() {
+                    quote! { serde::Serialize::serialize(&ArrayOf(self.raw_value), serializer) }
+                } else {
+                    quote! { serde::Serialize::serialize(&self.raw_value, serializer) }
+                };
+                let deserialized_raw_value = match &base_array {
+                    Some((element_type, element_size)) => {
+                        let element_count = base_data_size / element_size;
+                        quote! { <ArrayOf<#element_type, #element_count> as serde::Deserialize>::deserialize(deserializer)?.0 }
+                    }
+                    None if is_arbitrary_base => {
+                        let max_value = syn::LitInt::new(
+                            format!("0x{:X}", (1u128 << base_data_size) - 1).as_str(),
+                            proc_macro2::Span::call_site(),
+                        );
+                        quote! {
+                        {
+                            let value = <#storage_data_type as serde::Deserialize>::deserialize(deserializer)?;
+                            if value > #max_value {
+                                return Err(serde::de::Error::custom("raw value is out of range"));
+                            }
+                            arbitrary_int::#base_data_type::new(value)
+                        }
+                        }
+                    }
+                    None => {
+                        quote! { <#storage_data_type as serde::Deserialize>::deserialize(deserializer)? }
+                    }
+                };
+                let deserialize = if has_reserved_bits {
+                    quote! {
+                        Self::try_new_with_raw_value(#deserialized_raw_value).map_err(|_| serde::de::Error::custom("reserved bits don't have their required value"))
+                    }
+                } else {
+                    quote! { Ok(Self::new_with_raw_value(#deserialized_raw_value)) }
+                };
+                (serialize, deserialize)
+            };
+            let (serialize, deserialize, helpers) = match serde_mode {
+                SerdeMode::Raw => (raw_serialize, raw_deserialize, quote! {}),
+                SerdeMode::Fields => {
+                    let serialize_fields =
+                        field_outputs.iter().map(|output| &output.serialize_field);
+                    let readable_count = field_outputs
+                        .iter()
+                        .filter(|output| output.readable)
+                        .count();
+                    let deserialized_fields: Vec<&(String, TokenStream2)> = field_outputs
+                        .iter()
+                        .filter_map(|output| output.deserialize_field.as_ref())
+                        .collect();
+                    let field_names: Vec<&String> =
+                        deserialized_fields.iter().map(|(name, _)| name).collect();
+                    let field_indices: Vec<usize> = (0..deserialized_fields.len()).collect();
+                    let field_code = deserialized_fields.iter().map(|(_, code)| code);
+                    let serialize = quote! {
+                        if !serializer.is_human_readable() {
+                            return #raw_serialize;
+                        }
+                        use serde::ser::SerializeStruct;
+                        let mut state = serializer.serialize_struct(#struct_name_string, #readable_count)?;
+                        #( #serialize_fields )*
+                        state.end()
+                    };
+                    let deserialize = quote! {
+                        if !deserializer.is_human_readable() {
+                            return #raw_deserialize;
+                        }
+
+                        // Index of a field within FIELDS. Unknown fields are ignored
+                        struct FieldIndex(Option<usize>);
+                        impl<'de> serde::Deserialize<'de> for FieldIndex {
+                            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                                struct FieldIndexVisitor;
+                                impl<'de> serde::de::Visitor<'de> for FieldIndexVisitor {
+                                    type Value = FieldIndex;
+                                    fn expecting(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+                                        f.write_str("field name")
+                                    }
+                                    fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<FieldIndex, E> {
+                                        Ok(FieldIndex(FIELDS.iter().position(|name| *name == value)))
+                                    }
+                                }
+                                deserializer.deserialize_identifier(FieldIndexVisitor)
+                            }
+                        }
+
+                        struct FieldsVisitor;
+                        impl<'de> serde::de::Visitor<'de> for FieldsVisitor {
+                            type Value = #struct_name;
+                            fn expecting(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+                                f.write_str(concat!("struct ", #struct_name_string))
+                            }
+                            #[allow(unused_mut, unused_variables)]
+                            fn visit_map<A: serde::de::MapAccess<'de>>(self, mut map: A) -> Result<#struct_name, A::Error> {
+                                let mut raw_value = (#builder_initial_value).raw_value;
+                                while let Some(field_index) = map.next_key::<FieldIndex>()? {
+                                    match field_index.0 {
+                                        // Deserializes the field and writes it into raw_value
+                                        Some(index) => match index {
+                                            #( #field_indices => { #field_code } )*
+                                            _ => {}
+                                        },
+                                        None => {
+                                            map.next_value::<serde::de::IgnoredAny>()?;
+                                        }
+                                    }
+                                }
+                                Ok(#struct_name { raw_value })
+                            }
+                        }
+                        deserializer.deserialize_struct(#struct_name_string, FIELDS, FieldsVisitor)
+                    };
+                    let helper = quote! {
+                        // Names of the fields that can be deserialized, in the order of their indices
+                        const FIELDS: &[&str] = &[#( #field_names ),*];
+                    };
+                    (serialize, deserialize, helper)
+                }
+            };
+            // Helper types, which are only emitted if needed
+            let uses_array_of =
+                base_array.is_some() || field_outputs.iter().any(|output| output.is_array);
+            let uses_raw_or = field_outputs.iter().any(|output| output.is_option_type);
+            let array_of = if uses_array_of {
+                quote! {
+                    // Arrays of any length, serialized as tuple like regular arrays
+                    #[derive(Copy, Clone)]
+                    struct ArrayOf<T, const N: usize>([T; N]);
+
+                    impl<T: serde::Serialize, const N: usize> serde::Serialize for ArrayOf<T, N> {
+                        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                            use serde::ser::SerializeTuple;
+                            let mut tuple = serializer.serialize_tuple(N)?;
+                            for element in self.0.iter() {
+                                tuple.serialize_element(element)?;
+                            }
+                            tuple.end()
+                        }
+                    }
+
+                    impl<'de, T: serde::Deserialize<'de> + Copy, const N: usize> serde::Deserialize<'de> for ArrayOf<T, N> {
+                        fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                            struct ArrayVisitor<T, const N: usize>(core::marker::PhantomData<T>);
+                            impl<'de, T: serde::Deserialize<'de> + Copy, const N: usize> serde::de::Visitor<'de> for ArrayVisitor<T, N> {
+                                type Value = ArrayOf<T, N>;
+                                fn expecting(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+                                    write!(f, "an array of length {}", N)
+                                }
+                                fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                                    let mut values: [Option<T>; N] = [None; N];
+                                    for (i, value) in values.iter_mut().enumerate() {
+                                        *value = Some(seq.next_element()?.ok_or_else(|| serde::de::Error::invalid_length(i, &self))?);
+                                    }
+                                    Ok(ArrayOf(values.map(|value| value.unwrap())))
+                                }
+                            }
+                            deserializer.deserialize_tuple(N, ArrayVisitor(core::marker::PhantomData))
+                        }
+                    }
+                }
+            } else {
+                quote! {}
+            };
+            let raw_or = if uses_raw_or {
+                quote! {
+                    // Value of an Option<T> field: Either a valid value or the raw number. Only self-describing
+                    // formats get here, as the others use the raw value of the whole bitfield
+                    #[derive(Copy, Clone)]
+                    struct RawOr<T, R>(Result<T, R>);
+
+                    impl<T: serde::Serialize, R: serde::Serialize> serde::Serialize for RawOr<T, R> {
+                        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                            match &self.0 {
+                                Ok(value) => value.serialize(serializer),
+                                Err(raw) => raw.serialize(serializer),
+                            }
+                        }
+                    }
+
+                    impl<'de, T: serde::Deserialize<'de>, R: TryFrom<u64>> serde::Deserialize<'de> for RawOr<T, R> {
+                        fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                            struct RawOrVisitor<T, R>(core::marker::PhantomData<(T, R)>);
+                            impl<'de, T: serde::Deserialize<'de>, R: TryFrom<u64>> serde::de::Visitor<'de> for RawOrVisitor<T, R> {
+                                type Value = RawOr<T, R>;
+                                fn expecting(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+                                    f.write_str("variant name or raw value")
+                                }
+                                fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<Self::Value, E> {
+                                    T::deserialize(serde::de::value::StrDeserializer::<E>::new(value)).map(|value| RawOr(Ok(value)))
+                                }
+                                fn visit_u64<E: serde::de::Error>(self, value: u64) -> Result<Self::Value, E> {
+                                    R::try_from(value).map(|raw| RawOr(Err(raw))).map_err(|_| E::custom("raw value is out of range"))
+                                }
+                                fn visit_i64<E: serde::de::Error>(self, value: i64) -> Result<Self::Value, E> {
+                                    match u64::try_from(value) {
+                                        Ok(value) => self.visit_u64(value),
+                                        Err(_) => Err(E::custom("raw value is out of range")),
+                                    }
+                                }
+                            }
+                            deserializer.deserialize_any(RawOrVisitor(core::marker::PhantomData))
+                        }
+                    }
+
+                    // Stands in for an enum with an unknown value, so that its bits can be written like those of the enum
+                    struct RawBits<T>(T);
+
+                    impl<T> RawBits<T> {
+                        fn raw_value(self) -> T {
+                            self.0
+                        }
+                    }
+                }
+            } else {
+                quote! {}
+            };
+            quote! {
+                const _: () = {
+                    #array_of
+                    #raw_or
+                    #helpers
+
+                    impl serde::Serialize for #struct_name {
+                        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                            #serialize
+                        }
+                    }
+
+                    impl<'de> serde::Deserialize<'de> for #struct_name {
+                        fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                            #deserialize
+                        }
+                    }
+                };
+            }
+        }
+    };
+
+    // Wrapper for memory mapped registers. Registers without readable fields can only be written and
+    // registers without writable fields can only be read
+    let register_wrapper = if register {
+        let register_name = format_ident!("{}Register", struct_name);
+        let register_doc = format!(
+            "Memory mapped register that holds a {}. All accesses are volatile",
+            struct_name
+        );
+        let is_readable = field_outputs.iter().any(|output| output.readable);
+        let is_writable =
+            field_outputs.iter().any(|output| output.writable.is_some()) || has_side_effect_bits;
+        // Reading registers with clear on read fields has side effects, so they can't be modified
+        let is_read_clear = field_outputs.iter().any(|output| output.is_read_clear);
+        let read = if is_readable {
+            quote! {
+                /// Reads the register
+                #[inline]
+                pub fn read(&self) -> #struct_name {
+                    // Safety: The caller of new() guarantees that the pointer is valid
+                    unsafe { core::ptr::read_volatile(self.ptr) }
+                }
+            }
+        } else {
+            quote! {}
+        };
+        let write = if is_writable {
+            quote! {
+                /// Writes the register
+                #[inline]
+                pub fn write(&self, value: #struct_name) {
+                    // Safety: The caller of new() guarantees that the pointer is valid
+                    unsafe { core::ptr::write_volatile(self.ptr, value) }
+                }
+            }
+        } else {
+            quote! {}
+        };
+        let without_side_effects = if has_side_effect_bits {
+            quote! { .without_side_effects() }
+        } else {
+            quote! {}
+        };
+        let modify = if is_readable && is_writable && !is_read_clear {
+            quote! {
+                /// Reads the register, passes the value to f and writes back the value that f returns.
+                /// Fields with write side effects are neutralized before f is called.
+                #[inline]
+                pub fn modify<F: FnOnce(#struct_name) -> #struct_name>(&self, f: F) {
+                    self.write(f(self.read()#without_side_effects))
+                }
+            }
+        } else {
+            quote! {}
+        };
+        quote! {
+            #[doc = #register_doc]
+            #struct_vis struct #register_name {
+                ptr: *mut #struct_name,
+            }
+
+            impl #register_name {
+                /// Creates a new register at the given address.
+                ///
+                /// # Safety
+                ///
+                /// ptr has to be properly aligned and valid for volatile reads and writes for as long
+                /// as the register is used.
+                #[inline]
+                pub const unsafe fn new(ptr: *mut #struct_name) -> Self {
+                    Self { ptr }
+                }
+
+                /// Returns the address of the register
+                #[inline]
+                pub const fn as_ptr(&self) -> *mut #struct_name {
+                    self.ptr
+                }
+
+                #read
+                #write
+                #modify
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    // Array based bitfields get helpers to read and write bits across element boundaries
+    let array_helpers = match &base_array {
+        Some((element_type, element_size)) => quote! {
+            /// Returns number_of_bits bits of raw_value, starting at lowest_bit
+            #[inline]
+            #[allow(dead_code)]
+            const fn __bitbybit_extract_raw_bits(raw_value: &#base_data_type, lowest_bit: usize, number_of_bits: usize) -> u128 {
+                let mut result = 0u128;
+                let mut i = 0;
+                while i < number_of_bits {
+                    let bit = lowest_bit + i;
+                    let offset = bit % #element_size;
+                    let mut available = #element_size - offset;
+                    if available > number_of_bits - i {
+                        available = number_of_bits - i;
+                    }
+                    let chunk = ((raw_value[bit / #element_size] >> offset) as u128) & ((1u128 << available) - 1);
+                    result |= chunk << i;
+                    i += available;
+                }
+                result
+            }
+
+            /// Returns raw_value with number_of_bits bits starting at lowest_bit replaced by the lowest bits of value
+            #[inline]
+            #[allow(dead_code)]
+            const fn __bitbybit_insert_raw_bits(mut raw_value: #base_data_type, lowest_bit: usize, number_of_bits: usize, value: u128) -> #base_data_type {
+                let mut i = 0;
+                while i < number_of_bits {
+                    let bit = lowest_bit + i;
+                    let offset = bit % #element_size;
+                    let mut available = #element_size - offset;
+                    if available > number_of_bits - i {
+                        available = number_of_bits - i;
+                    }
+                    let mask = (((1u128 << available) - 1) as #element_type) << offset;
+                    let element = bit / #element_size;
+                    raw_value[element] = (raw_value[element] & !mask) | ((((value >> i) as #element_type) << offset) & mask);
+                    i += available;
+                }
+                raw_value
+            }
+        },
+        None => quote! {},
+    };
+
+    // Conversion from and to bytes. Arrays are converted element by element, starting with the least
+    // significant element
+    let byte_count = base_data_size / 8;
+    let (to_le_bytes, from_le_bytes) = match &base_array {
+        Some((element_type, element_size)) => {
+            let element_byte_count = element_size / 8;
+            let element_count = byte_count / element_byte_count;
+            (
+                quote! {
+                    let mut result = [0u8; #byte_count];
+                    let mut i = 0;
+                    while i < #element_count {
+                        let element_bytes = self.raw_value[i].to_le_bytes();
+                        let mut j = 0;
+                        while j < #element_byte_count {
+                            result[i * #element_byte_count + j] = element_bytes[j];
+                            j += 1;
+                        }
+                        i += 1;
+                    }
+                    result
+                },
+                quote! {
+                    let mut raw_value = [0; #element_count];
+                    let mut i = 0;
+                    while i < #element_count {
+                        let mut element_bytes = [0u8; #element_byte_count];
+                        let mut j = 0;
+                        while j < #element_byte_count {
+                            element_bytes[j] = bytes[i * #element_byte_count + j];
+                            j += 1;
+                        }
+                        raw_value[i] = #element_type::from_le_bytes(element_bytes);
+                        i += 1;
+                    }
+                    Self { raw_value }
+                },
+            )
+        }
+        None => (
+            quote! { self.raw_value.to_le_bytes() },
+            quote! { Self { raw_value: #base_data_type::from_le_bytes(bytes) } },
+        ),
+    };
+    let slice_error_name = format_ident!("{}FromBytesError", struct_name);
+    let byte_conversions = quote! {
+        /// Returns the raw value of this bitfield as bytes in little-endian byte order
+        #[inline]
+        pub const fn to_le_bytes(&self) -> [u8; #byte_count] {
+            #to_le_bytes
+        }
+
+        /// Returns the raw value of this bitfield as bytes in big-endian byte order
+        #[inline]
+        pub const fn to_be_bytes(&self) -> [u8; #byte_count] {
+            let le_bytes = self.to_le_bytes();
+            let mut result = [0u8; #byte_count];
+            let mut i = 0;
+            while i < #byte_count {
+                result[i] = le_bytes[#byte_count - 1 - i];
+                i += 1;
+            }
+            result
+        }
+
+        /// Creates a new instance of this bitfield from bytes in little-endian byte order.
+        ///
+        /// Like new_with_raw_value(), no checks are performed on the value.
+        #[inline]
+        pub const fn from_le_bytes(bytes: [u8; #byte_count]) -> Self {
+            #from_le_bytes
+        }
+
+        /// Creates a new instance of this bitfield from bytes in big-endian byte order.
+        ///
+        /// Like new_with_raw_value(), no checks are performed on the value.
+        #[inline]
+        pub const fn from_be_bytes(bytes: [u8; #byte_count]) -> Self {
+            let mut le_bytes = [0u8; #byte_count];
+            let mut i = 0;
+            while i < #byte_count {
+                le_bytes[i] = bytes[#byte_count - 1 - i];
+                i += 1;
+            }
+            Self::from_le_bytes(le_bytes)
+        }
+
+        /// Creates a new instance of this bitfield from the start of a slice in little-endian byte
+        /// order. Additional bytes (e.g. the payload that follows a header) are ignored.
+        ///
+        /// Returns an error if the slice is too short.
+        #[inline]
+        pub const fn from_le_slice(bytes: &[u8]) -> Result<Self, #slice_error_name> {
+            if bytes.len() < #byte_count {
+                return Err(#slice_error_name { length: bytes.len() });
+            }
+            let mut le_bytes = [0u8; #byte_count];
+            let mut i = 0;
+            while i < #byte_count {
+                le_bytes[i] = bytes[i];
+                i += 1;
+            }
+            Ok(Self::from_le_bytes(le_bytes))
+        }
+
+        /// Creates a new instance of this bitfield from the start of a slice in big-endian byte
+        /// order. Additional bytes (e.g. the payload that follows a header) are ignored.
+        ///
+        /// Returns an error if the slice is too short.
+        #[inline]
+        pub const fn from_be_slice(bytes: &[u8]) -> Result<Self, #slice_error_name> {
+            match Self::from_le_slice(bytes) {
+                Ok(value) => Ok(Self::from_be_bytes(value.to_le_bytes())),
+                Err(error) => Err(error),
+            }
+        }
+
+        /// Creates a new instance of this bitfield from the start of a slice in the byte order of
+        /// the target, like the bitfield is kept in memory. Use from_le_slice() or from_be_slice()
+        /// for data with a given byte order, e.g. network packets.
+        ///
+        /// Returns an error if the slice is too short.
+        #[inline]
+        pub const fn from_bytes(bytes: &[u8]) -> Result<Self, #slice_error_name> {
+            if cfg!(target_endian = "big") {
+                Self::from_be_slice(bytes)
+            } else {
+                Self::from_le_slice(bytes)
+            }
+        }
+    };
+    let slice_error_doc = format!(
+        "The error returned by {}::from_bytes(), from_le_slice() and from_be_slice() if the slice is shorter than {} bytes",
+        struct_name, byte_count
+    );
+    let slice_error_message = format!(
+        "slice is too short for {}, which needs {} bytes",
+        struct_name, byte_count
+    );
+    let slice_error = quote! {
+        #[doc = #slice_error_doc]
+        #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+        #struct_vis struct #slice_error_name {
+            /// The length of the slice
+            pub length: usize,
+        }
+
+        impl core::fmt::Display for #slice_error_name {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                core::write!(f, "{} ({} bytes)", #slice_error_message, self.length)
+            }
+        }
+    };
+
+    // Byte conversions are only provided for base data types that consist of whole bytes
+    let (byte_conversions, slice_error) = if is_arbitrary_base {
+        (quote! {}, quote! {})
+    } else {
+        (byte_conversions, slice_error)
+    };
+
+    let (raw_value_getter, raw_value_setter) = if is_arbitrary_base {
+        (
+            quote! { arbitrary_int::#base_data_type::new(self.raw_value) },
+            quote! { value.value() },
+        )
+    } else {
+        (quote! { self.raw_value }, quote! { value })
+    };
+
     let expanded = quote! {
         #[derive(Copy, Clone)]
         #[repr(C)]
         #( #struct_attrs )*
         #struct_vis struct #struct_name {
-            raw_value: #base_data_type,
+            raw_value: #storage_data_type,
         }
 
         impl #struct_name {
             #default_constructor
+            #builder_constructor
+            #reserved_functions
+            #side_effect_functions
+            #flag_constants
+            #array_helpers
             /// Returns the underlying raw value of this bitfield
             #[inline]
-            pub const fn raw_value(&self) -> #base_data_type { self.raw_value }
+            pub const fn raw_value(&self) -> #base_data_type { #raw_value_getter }
 
             /// Creates a new instance of this bitfield with the given raw value.
             ///
             /// No checks are performed on the value, so it is possible to set bits that don't have any
             /// accessors specified.
             #[inline]
-            pub const fn new_with_raw_value(value: #base_data_type) -> #struct_name { #struct_name { raw_value: value } }
+            pub const fn new_with_raw_value(value: #base_data_type) -> #struct_name { #struct_name { raw_value: #raw_value_setter } }
+            #byte_conversions
             #( #accessors )*
         }
+        #( #const_assertions )*
+        #slice_error
         #default_trait
+        #debug_trait
+        #defmt_trait
+        #flag_traits
+        #serde_implementations
+        #builder
+        #register_wrapper
     };
     // println!("Expanded: {}", expanded.to_string());
-    TokenStream::from(expanded)
+    Ok(expanded)
+}
+
+// The generated code can only be checked with the defmt feature, e.g. with
+// cargo test -p bitbybit --features defmt
+#[cfg(all(test, feature = "defmt"))]
+mod tests {
+    use super::generate;
+    use quote::quote;
+
+    // Returns the format string that defmt::Format is implemented with
+    fn defmt_format_string(input: proc_macro2::TokenStream) -> String {
+        let expanded = generate(quote! { u32, defmt }, input).unwrap().to_string();
+        let (_, write) = expanded.split_once("defmt :: write ! (f , ").unwrap();
+        let literal = write.strip_prefix('"').unwrap();
+        literal[..literal.find('"').unwrap()].to_string()
+    }
+
+    #[test]
+    fn defmt_format_string_escapes_braces_and_skips_write_only_fields() {
+        let format_string = defmt_format_string(quote! {
+            struct Test {
+                #[bits(24..=31, rw)]
+                r#type: u8,
+
+                #[bits(16..=23, w)]
+                command: u8,
+
+                #[bit(0, r)]
+                flag: [bool; 4],
+            }
+        });
+        assert_eq!("Test {{ type: {}, flag: {} }}", format_string);
+    }
+
+    #[test]
+    fn defmt_format_string_without_readable_fields() {
+        let format_string = defmt_format_string(quote! {
+            struct WriteOnly {
+                #[bits(0..=31, w)]
+                value: u32,
+            }
+        });
+        assert_eq!("WriteOnly", format_string);
+
+        let format_string = defmt_format_string(quote! {
+            struct Empty {}
+        });
+        assert_eq!("Empty", format_string);
+    }
 }
@@ -1,11 +1,22 @@
 use proc_macro::TokenStream;
 
+mod arguments;
 mod bitenum;
 mod bitfield;
 
-/// Defines a bitfield: #[bitfield(<base-data-type>, default: 0)]
+/// Defines a bitfield: #[bitfield(<base-data-type>, default: 0, bit_order: lsb0, debug, defmt, register, in_place_setters, flags, builder, serde: raw)]
 /// <base-data-type> is a data type like u32 which is used to represent all the bits of the bitfield.
+/// Arrays like [u8; 32] can be used for bitfields that are larger than 128 bits.
+/// Arbitrary ints like u5 are useful for bitfields that are nested within other bitfields.
 /// default is an optional default when the bitfield is created
+/// bit_order is optional and either lsb0 (the default) or msb0 (bit 0 is the most significant bit)
+/// debug is optional and generates a Debug implementation that prints every readable field
+/// defmt is optional (requires the defmt feature) and generates a defmt::Format implementation that prints every readable field
+/// register is optional and generates a <Name>Register wrapper for volatile memory mapped access
+/// in_place_setters is optional and generates set_<field>(&mut self, ..) next to with_<field>(&self, ..)
+/// flags is optional and generates bitwise operators as well as a constant for each single bit field
+/// builder is optional and generates a <Name>Builder that requires every writable field to be set exactly once
+/// serde is optional (requires the serde feature) and is either raw (serialize the raw value) or fields (serialize a map of fields)
 #[proc_macro_attribute]
 pub fn bitfield(args: TokenStream, input: TokenStream) -> TokenStream {
     bitfield::bitfield(args, input)